@@ -1,1662 +1,5683 @@
-use std::sync::Arc;
-use std::time::Duration;
-use std::collections::{HashMap, VecDeque};
-use tokio::time::Instant;
-use anyhow::Result;
-use anchor_client::solana_sdk::signature::Signature;
-use anchor_client::solana_sdk::signer::Signer;
-use anchor_client::solana_sdk::pubkey::Pubkey;
-use anchor_client::solana_sdk::system_instruction;
-use anchor_client::solana_sdk::transaction::Transaction;
-use colored::Colorize;
-use tokio::time;
-use tokio::sync::Mutex;
-use futures_util::stream::StreamExt;
-use futures_util::{SinkExt, Sink};
-use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
-use yellowstone_grpc_proto::geyser::{
-    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestPing,
-    SubscribeRequestFilterTransactions, SubscribeUpdate,
-};
-use crate::engine::transaction_parser;
-use crate::common::{
-    config::{AppState, SwapConfig, JUPITER_PROGRAM, OKX_DEX_PROGRAM},
-    logger::Logger,
-    wallet_pool::{WalletPool, RandomizationConfig, TradeType},
-    price_monitor::{GlobalPriceMonitor, create_global_price_monitor},
-    dynamic_ratios::{GlobalDynamicRatioManager, create_global_dynamic_ratio_manager},
-    volume_waves::{GlobalVolumeWaveManager, create_global_volume_wave_manager},
-    guardian_mode::{GlobalGuardianMode, create_global_guardian_mode},
-};
-use crate::dex::raydium_cpmm::RaydiumCPMM;
-use crate::engine::swap::{SwapDirection, SwapInType};
-use crate::core::token;
-use spl_token::instruction::sync_native;
-use spl_associated_token_account::get_associated_token_address;
-use solana_program_pack::Pack;
-use std::str::FromStr;
-use rand::Rng;
-use crate::engine::transaction_parser::{parse_target_token_transaction, TradeInfoFromToken};
-
-// Activity tracking structures for token analysis
-#[derive(Debug, Clone)]
-pub struct TokenActivity {
-    pub timestamp: Instant,
-    pub is_buy: bool,
-    pub volume_sol: f64,
-    pub user: String,
-    pub price: f64,
-}
-
-#[derive(Debug, Default)]
-pub struct TokenActivityReport {
-    pub total_trades: u32,
-    pub buy_trades: u32,
-    pub sell_trades: u32,
-    pub total_volume_sol: f64,
-    pub buy_volume_sol: f64,
-    pub sell_volume_sol: f64,
-    pub average_price: f64,
-    pub min_price: f64,
-    pub max_price: f64,
-    pub unique_traders: u32,
-    pub report_period_minutes: u64,
-}
-
-/// Configuration for market maker bot with advanced multi-wallet support
-#[derive(Clone)]
-pub struct MarketMakerConfig {
-    pub yellowstone_grpc_http: String,
-    pub yellowstone_grpc_token: String,
-    pub app_state: Arc<AppState>,
-    pub target_token_mint: String,
-    pub slippage: u64,
-    pub randomization_config: RandomizationConfig,
-    pub enable_multi_wallet: bool,
-    pub max_concurrent_trades: usize,
-    pub enable_telegram_notifications: bool,
-}
-
-impl MarketMakerConfig {
-    /// Create a new MarketMakerConfig with stealth mode settings
-    pub fn stealth_mode(
-        yellowstone_grpc_http: String,
-        yellowstone_grpc_token: String,
-        app_state: Arc<AppState>,
-        target_token_mint: String,
-    ) -> Self {
-        Self {
-            yellowstone_grpc_http,
-            yellowstone_grpc_token,
-            app_state,
-            target_token_mint,
-            slippage: 1000, // 10%
-            randomization_config: RandomizationConfig::stealth_mode(),
-            enable_multi_wallet: true,
-            max_concurrent_trades: 3,
-            enable_telegram_notifications: true,
-        }
-    }
-
-    /// Create a new MarketMakerConfig with conservative settings
-    pub fn conservative_mode(
-        yellowstone_grpc_http: String,
-        yellowstone_grpc_token: String,
-        app_state: Arc<AppState>,
-        target_token_mint: String,
-    ) -> Self {
-        Self {
-            yellowstone_grpc_http,
-            yellowstone_grpc_token,
-            app_state,
-            target_token_mint,
-            slippage: 1500, // 15%
-            randomization_config: RandomizationConfig::conservative_mode(),
-            enable_multi_wallet: true,
-            max_concurrent_trades: 2,
-            enable_telegram_notifications: true,
-        }
-    }
-
-    /// Create a new MarketMakerConfig with default settings
-    pub fn new(
-        yellowstone_grpc_http: String,
-        yellowstone_grpc_token: String,
-        app_state: Arc<AppState>,
-        target_token_mint: String,
-    ) -> Self {
-        Self {
-            yellowstone_grpc_http,
-            yellowstone_grpc_token,
-            app_state,
-            target_token_mint,
-            slippage: 1000, // 10%
-            randomization_config: RandomizationConfig::default(),
-            enable_multi_wallet: true,
-            max_concurrent_trades: 2,
-            enable_telegram_notifications: true,
-        }
-    }
-}
-
-/// Advanced market maker bot with multi-wallet support and sophisticated randomization
-pub struct MarketMaker {
-    config: MarketMakerConfig,
-    wallet_pool: Arc<Mutex<WalletPool>>,
-    raydium_cpmm: RaydiumCPMM,
-    logger: Logger,
-    is_running: Arc<tokio::sync::RwLock<bool>>,
-    recent_trades: Arc<Mutex<VecDeque<TradeType>>>,
-    trade_counter: Arc<Mutex<u32>>,
-    current_wallet: Arc<Mutex<Option<Arc<anchor_client::solana_sdk::signature::Keypair>>>>,
-    wallet_change_counter: Arc<Mutex<u32>>,
-    token_activities: Arc<Mutex<VecDeque<TokenActivity>>>,
-    last_activity_report: Arc<Mutex<Instant>>,
-    price_monitor: GlobalPriceMonitor,
-    dynamic_ratio_manager: GlobalDynamicRatioManager,
-    volume_wave_manager: GlobalVolumeWaveManager,
-    guardian_mode: GlobalGuardianMode,
-}
-
-impl MarketMaker {
-    /// Create a new advanced market maker instance
-    pub fn new(config: MarketMakerConfig) -> Result<Self, String> {
-        let wallet_pool = WalletPool::new()?;
-        let wallet_count = wallet_pool.wallet_count();
-        let wallet_pool = Arc::new(Mutex::new(wallet_pool));
-
-        let raydium_cpmm = RaydiumCPMM::new(
-            config.app_state.wallet.clone(),
-            Some(config.app_state.rpc_client.clone()),
-            Some(config.app_state.rpc_nonblocking_client.clone()),
-        );
-
-        let logger = Logger::new("[STEALTH-MARKET-MAKER] => ".green().bold().to_string());
-
-        logger.log(format!("🎯 Advanced Market Maker initialized with {} wallets", wallet_count).green().bold().to_string());
-
-        // Create price monitor with default threshold of 15%
-        let price_monitor = create_global_price_monitor(0.15);
-        
-        // Create dynamic ratio manager with weekly changes (168 hours)
-        let dynamic_ratio_manager = create_global_dynamic_ratio_manager(0.67, 0.73, 168);
-        
-        // Create volume wave manager with 2 hour active, 6 hour slow cycles
-        let volume_wave_manager = create_global_volume_wave_manager(2, 6);
-        
-        // Create guardian mode with 10% drop threshold
-        let guardian_mode = create_global_guardian_mode(true, 0.10);
-
-        Ok(Self {
-            config,
-            wallet_pool,
-            raydium_cpmm,
-            logger,
-            is_running: Arc::new(tokio::sync::RwLock::new(false)),
-            recent_trades: Arc::new(Mutex::new(VecDeque::with_capacity(20))),
-            trade_counter: Arc::new(Mutex::new(0)),
-            current_wallet: Arc::new(Mutex::new(None)),
-            wallet_change_counter: Arc::new(Mutex::new(0)),
-            token_activities: Arc::new(Mutex::new(VecDeque::with_capacity(20))),
-            last_activity_report: Arc::new(Mutex::new(Instant::now())),
-            price_monitor,
-            dynamic_ratio_manager,
-            volume_wave_manager,
-            guardian_mode,
-        })
-    }
-
-    /// Start the advanced market maker bot
-    pub async fn start(&self) -> Result<(), String> {
-        {
-            let mut running = self.is_running.write().await;
-            if *running {
-                return Err("Market maker is already running".to_string());
-            }
-            *running = true;
-        }
-
-        self.logger.log("🚀 Starting Advanced Stealth Market Maker...".green().bold().to_string());
-        self.logger.log(format!("Target token: {}", self.config.target_token_mint));
-        self.logger.log(format!("Buy amount ratio: {:.1}% - {:.1}% of wrapped WSOL", 
-            self.config.randomization_config.min_amount_sol * 100.0, 
-            self.config.randomization_config.max_amount_sol * 100.0));
-        self.logger.log(format!("Buy/Sell ratio: {:.0}% buy / {:.0}% sell", 
-            self.config.randomization_config.buy_sell_ratio * 100.0,
-            (1.0 - self.config.randomization_config.buy_sell_ratio) * 100.0));
-        self.logger.log(format!("Wallet rotation: Every {} trades", 
-            self.config.randomization_config.wallet_rotation_frequency));
-        self.logger.log(format!("Max concurrent trades: {}", self.config.max_concurrent_trades));
-
-        // Initialize first wallet
-        {
-            let mut wallet_pool = self.wallet_pool.lock().await;
-            let first_wallet = wallet_pool.get_random_wallet();
-            let mut current_wallet = self.current_wallet.lock().await;
-            *current_wallet = Some(first_wallet.clone());
-            self.logger.log(format!("🔑 Starting with wallet: {}", first_wallet.pubkey()));
-        }
-
-        // Start GRPC streaming for token monitoring
-        let grpc_task = self.start_grpc_monitoring();
-        
-        // Start the unified trading engine
-        let trading_task = self.start_advanced_trading_engine();
-
-        // Run all tasks concurrently
-        tokio::select! {
-            result = grpc_task => {
-                if let Err(e) = result {
-                    self.logger.log(format!("GRPC monitoring failed: {}", e).red().to_string());
-                }
-            }
-            result = trading_task => {
-                if let Err(e) = result {
-                    self.logger.log(format!("Trading engine failed: {}", e).red().to_string());
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Stop the market maker bot
-    pub async fn stop(&self) {
-        let mut running = self.is_running.write().await;
-        *running = false;
-        self.logger.log("Advanced Market Maker stopped".red().to_string());
-    }
-
-    /// Check if the market maker is running
-    pub async fn is_running(&self) -> bool {
-        *self.is_running.read().await
-    }
-
-    /// Advanced trading engine with sophisticated randomization
-    async fn start_advanced_trading_engine(&self) -> Result<(), String> {
-        self.logger.log("🎰 Starting Advanced Trading Engine...".cyan().bold().to_string());
-
-        while self.is_running().await {
-                    // Determine next trade type based on recent history with dynamic ratio and guardian mode
-        let should_buy = {
-            let recent_trades = self.recent_trades.lock().await;
-            let trades_vec: Vec<TradeType> = recent_trades.iter().copied().collect();
-            let wallet_pool = self.wallet_pool.lock().await;
-            
-            // Get current dynamic buy ratio
-            let mut dynamic_ratio_manager = self.dynamic_ratio_manager.lock().await;
-            let mut current_buy_ratio = dynamic_ratio_manager.get_current_buy_ratio();
-            
-            // Apply guardian mode bias if active
-            let guardian_mode = self.guardian_mode.lock().await;
-            let guardian_buy_bias = guardian_mode.get_buy_bias();
-            if guardian_buy_bias > 0.0 {
-                current_buy_ratio = (current_buy_ratio + guardian_buy_bias).min(0.95); // Cap at 95%
-                self.logger.log(format!(
-                    "🛡️ Guardian mode applying buy bias: +{:.1}% (Total ratio: {:.1}%)",
-                    guardian_buy_bias * 100.0,
-                    current_buy_ratio * 100.0
-                ).red().to_string());
-            }
-            
-            wallet_pool.should_buy_next(&trades_vec, current_buy_ratio)
-        };
-
-            // Check if we need to rotate wallet
-            let should_rotate_wallet = {
-                let wallet_change_counter = self.wallet_change_counter.lock().await;
-                *wallet_change_counter >= self.config.randomization_config.wallet_rotation_frequency
-            };
-
-            if should_rotate_wallet {
-                self.rotate_wallet().await;
-            }
-
-            // Execute the trade
-            if should_buy {
-                // Execute stealth buy with proper amount calculation
-                if let Err(e) = self.execute_advanced_buy_debug(0.0).await {
-                    self.logger.log(format!("❌ Advanced buy failed: {}", e).red().to_string());
-                }
-            } else {
-                // Generate random sell percentage (10% to 50%)
-                let sell_percentage = 0.1 + (rand::random::<f64>() * 0.4);
-                if let Err(e) = self.execute_advanced_sell(sell_percentage).await {
-                    self.logger.log(format!("❌ Advanced sell failed: {}", e).red().to_string());
-                }
-            }
-
-            // Generate next interval with price-based throttling, volume waves, and guardian mode
-            let next_interval = {
-                let wallet_pool = self.wallet_pool.lock().await;
-                let price_monitor = self.price_monitor.lock().await;
-                let mut volume_wave_manager = self.volume_wave_manager.lock().await;
-                let guardian_mode = self.guardian_mode.lock().await;
-                
-                let base_interval = if should_buy {
-                    self.config.randomization_config.base_buy_interval_ms
-                } else {
-                    self.config.randomization_config.base_sell_interval_ms
-                };
-                
-                // Get raw interval with wallet pool randomization
-                let raw_interval = wallet_pool.generate_random_interval(base_interval);
-                
-                // Apply price-based throttling
-                let throttling_multiplier = price_monitor.get_throttling_multiplier();
-                let throttled_interval = (raw_interval as f64 * throttling_multiplier) as u64;
-                
-                // Apply volume wave patterns
-                let current_phase = volume_wave_manager.get_current_phase();
-                let wave_interval = volume_wave_manager.get_natural_interval(throttled_interval);
-                
-                // Apply guardian mode acceleration
-                let guardian_multiplier = guardian_mode.get_frequency_multiplier();
-                let final_interval = (wave_interval as f64 * guardian_multiplier) as u64;
-                
-                // Log comprehensive status when multiple systems are active
-                let is_complex = throttling_multiplier != 1.0 || guardian_multiplier != 1.0 || guardian_mode.is_active();
-                if is_complex {
-                    self.logger.log(format!(
-                        "⚡ Complex interval: Phase: {:?} | Price: {:.1}x | Guardian: {:.1}x | Final: {:.1}min",
-                        current_phase,
-                        throttling_multiplier,
-                        guardian_multiplier,
-                        final_interval as f64 / 60000.0
-                    ).cyan().to_string());
-                }
-                
-                final_interval
-            };
-
-                                if next_interval > 600000 {
-                        self.logger.log(format!("🐌 Price throttling active - Next trade in {:.1} minutes", next_interval as f64 / 60000.0).red().to_string());
-                    } else {
-                        self.logger.log(format!("⏰ Next trade in {:.1} minutes", next_interval as f64 / 60000.0).yellow().to_string());
-                    }
-            
-            // Check and log activity report if it's time
-            self.check_and_log_activity_report().await;
-            
-            // Wait for next trade
-            time::sleep(Duration::from_millis(next_interval)).await;
-
-            if !self.is_running().await {
-                break;
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Rotate to a new wallet
-    async fn rotate_wallet(&self) {
-        let new_wallet = {
-            let mut wallet_pool = self.wallet_pool.lock().await;
-            wallet_pool.get_random_wallet()
-        };
-
-        {
-            let mut current_wallet = self.current_wallet.lock().await;
-            *current_wallet = Some(new_wallet.clone());
-        }
-
-        {
-            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
-            *wallet_change_counter = 0;
-        }
-
-        self.logger.log(format!("🔄 Rotated to wallet: {}", new_wallet.pubkey()).magenta().to_string());
-    }
-
-    /// Execute an advanced buy transaction with separated steps for debugging
-    async fn execute_advanced_buy_debug(&self, _amount_sol: f64) -> Result<Signature, String> {
-        let start_time = Instant::now();
-        
-        let current_wallet = {
-            let current_wallet = self.current_wallet.lock().await;
-            current_wallet.clone().ok_or("No current wallet set")?
-        };
-
-        let wallet_pubkey = current_wallet.pubkey();
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        
-        // Parse target token mint
-        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
-            .map_err(|e| format!("Invalid target token mint: {}", e))?;
-        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
-
-        // Get current SOL balance
-        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
-        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
-
-        self.logger.log(format!("🔍 INITIAL SOL Balance: {:.6} SOL ({} lamports)", sol_balance_f64, sol_balance).cyan().to_string());
-
-        // Check if accounts exist
-        let wsol_exists = self.config.app_state.rpc_client.get_account(&wsol_account).is_ok();
-        let target_token_exists = self.config.app_state.rpc_client.get_account(&target_token_account).is_ok();
-
-        self.logger.log(format!("🔍 Account Status - WSOL exists: {}, Target token exists: {}", wsol_exists, target_token_exists).cyan().to_string());
-
-        // Step 1: Create WSOL account if needed
-        if !wsol_exists {
-            self.logger.log("🔧 Step 1: Creating WSOL account...".yellow().to_string());
-            
-            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                .map_err(|e| format!("Failed to get balance before WSOL creation: {}", e))?;
-            
-            match self.create_wsol_account_only(&current_wallet).await {
-                Ok(()) => {
-                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                        .map_err(|e| format!("Failed to get balance after WSOL creation: {}", e))?;
-                    let cost = balance_before - balance_after;
-                    self.logger.log(format!("✅ Step 1 SUCCESS - WSOL account created. Cost: {:.6} SOL", cost as f64 / 1_000_000_000.0).green().to_string());
-                },
-                Err(e) => {
-                    self.logger.log(format!("❌ Step 1 FAILED - WSOL account creation failed: {}", e).red().to_string());
-                    return Err(format!("Step 1 failed: {}", e));
-                }
-            }
-        } else {
-            self.logger.log("✅ Step 1 SKIPPED - WSOL account already exists".green().to_string());
-        }
-
-        // Step 2: Create target token account if needed
-        if !target_token_exists {
-            self.logger.log("🔧 Step 2: Creating target token account...".yellow().to_string());
-            
-            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                .map_err(|e| format!("Failed to get balance before target token creation: {}", e))?;
-            
-            match self.create_target_token_account(&current_wallet, &target_token_mint).await {
-                Ok(()) => {
-                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                        .map_err(|e| format!("Failed to get balance after target token creation: {}", e))?;
-                    let cost = balance_before - balance_after;
-                    self.logger.log(format!("✅ Step 2 SUCCESS - Target token account created. Cost: {:.6} SOL", cost as f64 / 1_000_000_000.0).green().to_string());
-                },
-                Err(e) => {
-                    self.logger.log(format!("❌ Step 2 FAILED - Target token account creation failed: {}", e).red().to_string());
-                    return Err(format!("Step 2 failed: {}", e));
-                }
-            }
-        } else {
-            self.logger.log("✅ Step 2 SKIPPED - Target token account already exists".green().to_string());
-        }
-
-        // Step 3: Smart SOL/WSOL Balance Management
-        self.logger.log("🔧 Step 3: Smart balance management...".yellow().to_string());
-        
-        let current_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-            .map_err(|e| format!("Failed to get current balance: {}", e))?;
-        let current_balance_f64 = current_balance as f64 / 1_000_000_000.0;
-        
-        // Get WSOL balance
-        let wsol_balance = match self.config.app_state.rpc_client.get_account(&wsol_account) {
-            Ok(account) => {
-                match spl_token::state::Account::unpack(&account.data) {
-                    Ok(token_account) => token_account.amount as f64 / 1_000_000_000.0,
-                    Err(_) => 0.0,
-                }
-            },
-            Err(_) => 0.0,
-        };
-        
-        // Read balance thresholds from config (will get from environment variables)
-        // TODO: Get these from global config - for now use hardcoded values
-        let minimal_balance_for_fee = 0.005; // Reduced threshold - 0.005 SOL should be enough for fees
-        let minimal_wsol_balance_for_trading = 0.001; // Will be read from env
-        let critical_sol_threshold = 0.003; // Critical threshold - below this, definitely need to unwrap
-        
-        self.logger.log(format!("💰 Step 3 - SOL: {:.6}, WSOL: {:.6}, Critical SOL: {:.6}, WSOL threshold: {:.6}", 
-            current_balance_f64, wsol_balance, critical_sol_threshold, minimal_wsol_balance_for_trading).cyan().to_string());
-        
-                if current_balance_f64 > critical_sol_threshold && wsol_balance > minimal_wsol_balance_for_trading {
-            // Case 1: Sufficient SOL and WSOL - don't wrap, use existing WSOL
-            self.logger.log("✅ Step 3 SKIPPED - Sufficient SOL and WSOL balances, no wrapping needed".green().to_string());
-        } else if current_balance_f64 <= critical_sol_threshold && wsol_balance > minimal_wsol_balance_for_trading {
-             // Case 2: Low SOL but sufficient WSOL - unwrap some WSOL to SOL for fees
-             // Note: unwrapping also returns rent exemption (~0.00204 SOL), so we can unwrap less
-             let needed_sol = minimal_balance_for_fee - current_balance_f64;
-             let rent_exemption_bonus = 0.00204; // Approximate rent exemption we'll get back
-             let unwrap_amount = (needed_sol - rent_exemption_bonus).max(0.0001); // Minimum 0.0001 WSOL unwrap
-             
-             self.logger.log(format!("🔄 Step 3 - Low SOL, unwrapping {:.6} WSOL to SOL for fees (will get ~{:.6} SOL total)", 
-                 unwrap_amount, unwrap_amount + rent_exemption_bonus).yellow().to_string());
-             
-             if wsol_balance >= unwrap_amount {
-                let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                    .map_err(|e| format!("Failed to get balance before unwrap: {}", e))?;
-                
-                match self.unwrap_wsol_to_sol(&current_wallet, unwrap_amount).await {
-                    Ok(()) => {
-                        let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                            .map_err(|e| format!("Failed to get balance after unwrap: {}", e))?;
-                        let gained = balance_after - balance_before;
-                        self.logger.log(format!("✅ Step 3 SUCCESS - WSOL unwrapped to SOL. Amount: {:.6} WSOL, SOL gained: {:.6}", 
-                            unwrap_amount, gained as f64 / 1_000_000_000.0).green().to_string());
-                    },
-                    Err(e) => {
-                        self.logger.log(format!("❌ Step 3 FAILED - WSOL unwrapping failed: {}", e).red().to_string());
-                        return Err(format!("Step 3 failed: {}", e));
-                    }
-                }
-            } else {
-                return Err(format!("Insufficient WSOL for unwrapping. Need: {:.6}, Have: {:.6}", unwrap_amount, wsol_balance));
-            }
-        } else {
-            // Case 3: Need to wrap SOL to WSOL (original logic)
-            let reserve_for_fees = 0.0005; // Reserve for transaction fees
-            let available_sol = current_balance_f64 - reserve_for_fees;
-            
-            if available_sol <= 0.0 {
-                return Err(format!("Insufficient SOL for wrapping. Current: {:.6}, Reserved: {:.6}", current_balance_f64, reserve_for_fees));
-            }
-            
-            let wrap_amount = available_sol * 0.75; // Use 75% of available SOL
-            
-            self.logger.log(format!("🔧 Step 3 - Wrapping {:.6} SOL to WSOL", wrap_amount).yellow().to_string());
-            
-            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                .map_err(|e| format!("Failed to get balance before wrap: {}", e))?;
-            
-            match self.wrap_sol_to_wsol(&current_wallet, wrap_amount).await {
-                Ok(()) => {
-                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                        .map_err(|e| format!("Failed to get balance after wrap: {}", e))?;
-                    let cost = balance_before - balance_after;
-                    self.logger.log(format!("✅ Step 3 SUCCESS - SOL wrapped to WSOL. Amount: {:.6} SOL, Total cost: {:.6} SOL", 
-                        wrap_amount, cost as f64 / 1_000_000_000.0).green().to_string());
-                },
-                Err(e) => {
-                    self.logger.log(format!("❌ Step 3 FAILED - SOL wrapping failed: {}", e).red().to_string());
-                    return Err(format!("Step 3 failed: {}", e));
-                }
-            }
-        }
-
-        // Step 4: Execute swap
-        self.logger.log("🔧 Step 4: Executing swap...".yellow().to_string());
-        
-        // Get WSOL balance after balance management
-        let wsol_balance_after_management = match self.config.app_state.rpc_client.get_account(&wsol_account) {
-            Ok(account) => {
-                match spl_token::state::Account::unpack(&account.data) {
-                    Ok(token_account) => token_account.amount as f64 / 1_000_000_000.0,
-                    Err(_) => 0.0,
-                }
-            },
-            Err(_) => 0.0,
-        };
-        
-        if wsol_balance_after_management <= 0.0 {
-            return Err("No WSOL balance available for swap".to_string());
-        }
-        
-        // Calculate buy amount based on current WSOL balance (after smart management)
-        let mut rng = rand::thread_rng();
-        let random_multiplier = self.config.randomization_config.min_amount_sol + 
-            (self.config.randomization_config.max_amount_sol - self.config.randomization_config.min_amount_sol) * rng.gen::<f64>();
-        let final_buy_amount = wsol_balance_after_management * random_multiplier;
-        
-        self.logger.log(format!("🎯 Step 4 - WSOL Balance: {:.6}, Multiplier: {:.3}, Buy Amount: {:.6} SOL", 
-            wsol_balance_after_management, random_multiplier, final_buy_amount).cyan().to_string());
-        
-        // Create swap configuration
-        let swap_config = SwapConfig {
-            mint: self.config.target_token_mint.clone(),
-            swap_direction: SwapDirection::Buy,
-            in_type: SwapInType::Qty,
-            amount_in: final_buy_amount,
-            slippage: self.config.slippage,
-            max_buy_amount: final_buy_amount,
-        };
-
-        // Create RaydiumCPMM instance with current wallet
-        let raydium_cpmm = RaydiumCPMM::new(
-            current_wallet.clone(),
-            Some(self.config.app_state.rpc_client.clone()),
-            Some(self.config.app_state.rpc_nonblocking_client.clone()),
-        );
-
-        // Build and execute swap
-        let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-            .map_err(|e| format!("Failed to get balance before swap: {}", e))?;
-        
-        match raydium_cpmm.build_swap_from_default_info(swap_config).await {
-            Ok((keypair, instructions, token_price)) => {
-                self.logger.log(format!("Token price: ${:.8}", token_price));
-                
-                // Get recent blockhash for the skip simulation transaction
-                let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-                    .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-                
-                // Use the new skip simulation function for testing on-chain behavior
-                match crate::core::tx::new_signed_and_send_skip_simulation_force(
-                    self.config.app_state.rpc_nonblocking_client.clone(),
-                    recent_blockhash,
-                    &keypair,
-                    instructions,
-                    &self.logger,
-                ).await {
-                    Ok(signatures) => {
-                        let signature = signatures[0];
-                        let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-                            .map_err(|e| format!("Failed to get balance after swap: {}", e))?;
-                        let cost = balance_before - balance_after;
-                        
-                        self.logger.log(format!("✅ Step 4 SUCCESS - Swap executed with SKIP SIMULATION. Amount: {:.6} SOL, Cost: {:.6} SOL, Signature: {}", 
-                            final_buy_amount, cost as f64 / 1_000_000_000.0, signature).green().to_string());
-                        
-                        // Update trade tracking
-                        {
-                            let mut recent_trades = self.recent_trades.lock().await;
-                            recent_trades.push_back(TradeType::Buy);
-                            if recent_trades.len() > 20 {
-                                recent_trades.pop_front();
-                            }
-                        }
-
-                        {
-                            let mut trade_counter = self.trade_counter.lock().await;
-                            *trade_counter += 1;
-                        }
-
-                        {
-                            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
-                            *wallet_change_counter += 1;
-                        }
-
-                        self.logger.log(format!(
-                            "🎉 DEBUG BUY COMPLETED with SKIP SIMULATION! Total time: {:?}",
-                            start_time.elapsed()
-                        ).green().bold().to_string());
-                        
-                        Ok(signature)
-                    },
-                    Err(e) => {
-                        self.logger.log(format!("❌ Step 4 FAILED - ON-CHAIN transaction failed (this is the real error): {}", e).red().to_string());
-                        Err(format!("Step 4 failed: {}", e))
-                    }
-                }
-            },
-            Err(e) => {
-                self.logger.log(format!("❌ Step 4 FAILED - Swap building failed: {}", e).red().to_string());
-                Err(format!("Step 4 failed: {}", e))
-            }
-        }
-    }
-
-    /// Execute an advanced buy transaction with the current wallet
-    async fn execute_advanced_buy(&self, _amount_sol: f64) -> Result<Signature, String> {
-        let start_time = Instant::now();
-        
-        let current_wallet = {
-            let current_wallet = self.current_wallet.lock().await;
-            current_wallet.clone().ok_or("No current wallet set")?
-        };
-
-        let wallet_pubkey = current_wallet.pubkey();
-        
-        // Get current SOL balance
-        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
-        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
-        
-        // Check if we have enough SOL for operations
-        if sol_balance_f64 < 0.002 {
-            return Err(format!("Insufficient SOL balance: {} SOL", sol_balance_f64));
-        }
-        
-        // Calculate amount to wrap to WSOL (85% of available SOL, keeping 15% for fees)
-        let fee_reserve = 0.0015; // Reserve for transaction fees
-        let available_sol = sol_balance_f64 - fee_reserve;
-        let wrap_amount = if available_sol > 0.0 {
-            available_sol * 0.85 // Wrap 85% of available SOL
-        } else {
-            return Err("Insufficient SOL for wrapping".to_string());
-        };
-        
-        // Calculate buy amount based on WSOL balance (after wrapping, WSOL balance = wrap_amount)
-        // Apply randomization ratio directly to the WSOL balance
-        let wsol_balance_after_wrap = wrap_amount; // This will be the WSOL balance after wrapping
-        
-        // Get ratio range from config (these are ratios between 0 and 1)
-        let min_ratio = self.config.randomization_config.min_amount_sol.max(0.1).min(1.0);
-        let max_ratio = self.config.randomization_config.max_amount_sol.max(min_ratio).min(1.0);
-        
-        let mut rng = rand::thread_rng();
-        let random_multiplier = min_ratio + (max_ratio - min_ratio) * rng.gen::<f64>();
-        let final_buy_amount = wsol_balance_after_wrap * random_multiplier *0.1; // for me to see what happend 
-        
-        // Get WSOL and target token account addresses
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
-            .map_err(|e| format!("Invalid target token mint: {}", e))?;
-        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
-        
-        // Check if accounts exist
-        let wsol_exists = self.config.app_state.rpc_client.get_account(&wsol_account).is_ok();
-        let target_token_exists = self.config.app_state.rpc_client.get_account(&target_token_account).is_ok();
-        
-        // Start building instructions
-        let mut instructions = Vec::new();
-        
-        // Create WSOL account if needed
-        if !wsol_exists {
-            let create_wsol_instruction = spl_associated_token_account::instruction::create_associated_token_account(
-                &wallet_pubkey,  // payer
-                &wallet_pubkey,  // owner
-                &spl_token::native_mint::id(), // mint
-                &spl_token::id(), // token program
-            );
-            instructions.push(create_wsol_instruction);
-            self.logger.log("🔧 Added WSOL account creation instruction".yellow().to_string());
-        }
-        
-        // Create target token account if needed
-        if !target_token_exists {
-            let create_target_token_instruction = spl_associated_token_account::instruction::create_associated_token_account(
-                &wallet_pubkey,  // payer
-                &wallet_pubkey,  // owner
-                &target_token_mint, // mint
-                &spl_token::id(), // token program
-            );
-            instructions.push(create_target_token_instruction);
-            self.logger.log("🔧 Added target token account creation instruction".yellow().to_string());
-        }
-        
-        // Wrap SOL to WSOL
-        let wrap_lamports = (wrap_amount * 1_000_000_000.0) as u64;
-        instructions.push(
-            system_instruction::transfer(
-                &wallet_pubkey,
-                &wsol_account,
-                wrap_lamports,
-            )
-        );
-        instructions.push(
-            sync_native(&spl_token::id(), &wsol_account)
-                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
-        );
-        
-        self.logger.log(format!("💰 SOL Balance: {:.6}, Available: {:.6}, Wrap: {:.6} SOL", 
-            sol_balance_f64, available_sol, wrap_amount).cyan().to_string());
-        self.logger.log(format!("🎯 Buy calculation: WSOL({:.6}) * {:.3} = {:.6} SOL", 
-            wsol_balance_after_wrap, random_multiplier, final_buy_amount).cyan().to_string());
-        self.logger.log(format!("🔥 STEALTH BUY - Wrap: {:.6} SOL, Buy: {:.6} SOL - Wallet: {}", 
-            wrap_amount, final_buy_amount, wallet_pubkey).green().bold().to_string());
-        
-        // Create swap configuration
-        let swap_config = SwapConfig {
-            mint: self.config.target_token_mint.clone(),
-            swap_direction: SwapDirection::Buy,
-            in_type: SwapInType::Qty,
-            amount_in: final_buy_amount,
-            slippage: self.config.slippage,
-            max_buy_amount: final_buy_amount,
-        };
-
-        // Create RaydiumCPMM instance with current wallet
-        let raydium_cpmm = RaydiumCPMM::new(
-            current_wallet.clone(),
-            Some(self.config.app_state.rpc_client.clone()),
-            Some(self.config.app_state.rpc_nonblocking_client.clone()),
-        );
-
-        // Build swap instructions only (not the full transaction)
-        let (_, swap_instructions, token_price) = raydium_cpmm
-            .build_swap_from_default_info(swap_config)
-            .await
-            .map_err(|e| format!("Failed to build buy transaction: {}", e))?;
-
-        self.logger.log(format!("Token price: ${:.8}", token_price));
-        
-        // Add swap instructions to our combined transaction
-        instructions.extend(swap_instructions);
-        
-        // Send the combined transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&wallet_pubkey),
-            &[current_wallet.as_ref()],
-            recent_blockhash,
-        );
-
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send combined transaction: {}", e))?;
-
-        // Update trade tracking
-        {
-            let mut recent_trades = self.recent_trades.lock().await;
-            recent_trades.push_back(TradeType::Buy);
-            if recent_trades.len() > 20 {
-                recent_trades.pop_front();
-            }
-        }
-
-        {
-            let mut trade_counter = self.trade_counter.lock().await;
-            *trade_counter += 1;
-        }
-
-        {
-            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
-            *wallet_change_counter += 1;
-        }
-
-        self.logger.log(format!(
-            "✅ STEALTH BUY SUCCESS! Wrapped: {:.6} SOL → WSOL, Used: {:.6} SOL ({:.1}%), Signature: {}, Time: {:?}",
-            wrap_amount, final_buy_amount, (final_buy_amount / wrap_amount * 100.0), signature, start_time.elapsed()
-        ).green().bold().to_string());
-
-        Ok(signature)
-    }
-
-    /// Execute an advanced sell transaction with the current wallet
-    async fn execute_advanced_sell(&self, percentage: f64) -> Result<Signature, String> {
-        let start_time = Instant::now();
-        
-        let current_wallet = {
-            let current_wallet = self.current_wallet.lock().await;
-            current_wallet.clone().ok_or("No current wallet set")?
-        };
-
-        // Check and prepare wallet (SOL, WSOL, Token balances)
-        self.check_and_prepare_wallet(&current_wallet).await?;
-
-        // Log wallet and WSOL account before trading
-        let wsol_account = get_associated_token_address(&current_wallet.pubkey(), &spl_token::native_mint::id());
-        self.logger.log(format!("🔥 STEALTH SELL - Percentage: {:.1}% - Wallet: {} - WSOL: {}", 
-            percentage * 100.0, current_wallet.pubkey(), wsol_account).blue().bold().to_string());
-
-        let swap_config = SwapConfig {
-            mint: self.config.target_token_mint.clone(),
-            swap_direction: SwapDirection::Sell,
-            in_type: SwapInType::Pct,
-            amount_in: percentage,
-            slippage: self.config.slippage,
-            max_buy_amount: 0.0,
-        };
-
-        // Create RaydiumCPMM instance with current wallet
-        let raydium_cpmm = RaydiumCPMM::new(
-            current_wallet.clone(),
-            Some(self.config.app_state.rpc_client.clone()),
-            Some(self.config.app_state.rpc_nonblocking_client.clone()),
-        );
-
-        // Build swap transaction
-        let (keypair, instructions, token_price) = raydium_cpmm
-            .build_swap_from_default_info(swap_config)
-            .await
-            .map_err(|e| format!("Failed to build sell transaction: {}", e))?;
-
-        self.logger.log(format!("Token price: ${:.8}", token_price));
-
-        // Send transaction
-        let signature = self.send_transaction(&keypair, instructions).await
-            .map_err(|e| format!("Failed to send sell transaction: {}", e))?;
-
-        // Update trade tracking
-        {
-            let mut recent_trades = self.recent_trades.lock().await;
-            recent_trades.push_back(TradeType::Sell);
-            if recent_trades.len() > 20 {
-                recent_trades.pop_front();
-            }
-        }
-
-        {
-            let mut trade_counter = self.trade_counter.lock().await;
-            *trade_counter += 1;
-        }
-
-        {
-            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
-            *wallet_change_counter += 1;
-        }
-
-        self.logger.log(format!(
-            "✅ STEALTH SELL SUCCESS! Percentage: {:.1}%, Signature: {}, Time: {:?}",
-            percentage * 100.0, signature, start_time.elapsed()
-        ).blue().bold().to_string());
-
-        Ok(signature)
-    }
-
-    /// Start GRPC monitoring for the target token
-    async fn start_grpc_monitoring(&self) -> Result<(), String> {
-        self.logger.log("🔍 Starting GRPC token monitoring...".cyan().to_string());
-
-        // Connect to Yellowstone gRPC
-        let mut client = GeyserGrpcClient::build_from_shared(self.config.yellowstone_grpc_http.clone())
-            .map_err(|e| format!("Failed to build GRPC client: {}", e))?
-            .x_token::<String>(Some(self.config.yellowstone_grpc_token.clone()))
-            .map_err(|e| format!("Failed to set x_token: {}", e))?
-            .tls_config(ClientTlsConfig::new().with_native_roots())
-            .map_err(|e| format!("Failed to set tls config: {}", e))?
-            .connect()
-            .await
-            .map_err(|e| format!("Failed to connect to GRPC: {}", e))?;
-
-        // Set up subscription
-        let mut retry_count = 0;
-        const MAX_RETRIES: u32 = 3;
-        let (subscribe_tx, mut stream) = loop {
-            match client.subscribe().await {
-                Ok(pair) => break pair,
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= MAX_RETRIES {
-                        return Err(format!("Failed to subscribe after {} attempts: {}", MAX_RETRIES, e));
-                    }
-                    self.logger.log(format!(
-                        "[CONNECTION ERROR] => Failed to subscribe (attempt {}/{}): {}. Retrying in 5 seconds...",
-                        retry_count, MAX_RETRIES, e
-                    ).red().to_string());
-                    time::sleep(Duration::from_secs(5)).await;
-                }
-            }
-        };
-
-        let subscribe_tx = Arc::new(tokio::sync::Mutex::new(subscribe_tx));
-
-        // Set up subscription for target token
-        let subscription_request = SubscribeRequest {
-            transactions: maplit::hashmap! {
-                "TargetToken".to_owned() => SubscribeRequestFilterTransactions {
-                    vote: Some(false),
-                    failed: Some(false),
-                    signature: None,
-                    account_include: vec!["CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C".to_string()],
-                    account_exclude: vec![JUPITER_PROGRAM.to_string(), OKX_DEX_PROGRAM.to_string()],
-                    account_required: Vec::<String>::new(),
-                }
-            },
-            commitment: Some(CommitmentLevel::Processed as i32),
-            ..Default::default()
-        };
-
-        subscribe_tx
-            .lock()
-            .await
-            .send(subscription_request)
-            .await
-            .map_err(|e| format!("Failed to send subscribe request: {}", e))?;
-
-        // Spawn heartbeat task
-        let subscribe_tx_clone = subscribe_tx.clone();
-        tokio::spawn(async move {
-            loop {
-                time::sleep(Duration::from_secs(30)).await;
-                if let Err(e) = send_heartbeat_ping(&subscribe_tx_clone).await {
-                    eprintln!("Heartbeat ping failed: {}", e);
-                }
-            }
-        });
-
-        // Process incoming messages
-        self.logger.log("✅ GRPC monitoring started, processing transactions...".green().to_string());
-        while let Some(msg) = stream.next().await {
-            if !self.is_running().await {
-                break;
-            }
-
-            match msg {
-                Ok(msg) => {
-                    if let Err(e) = self.process_grpc_message(&msg).await {
-                        self.logger.log(format!("Error processing message: {}", e).red().to_string());
-                    }
-                },
-                Err(e) => {
-                    self.logger.log(format!("Stream error: {}", e).red().to_string());
-                    break;
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Check and prepare wallet for trading (check balances, create/wrap WSOL if needed)
-    async fn check_and_prepare_wallet(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        
-        // Log current trading wallet
-        self.logger.log(format!("🔍 Current trading wallet: {}", wallet_pubkey).cyan().to_string());
-
-        // Get SOL balance
-        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
-            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
-        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
-        
-        // Get WSOL account address
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        
-        // Log WSOL account
-        self.logger.log(format!("🔍 WSOL account: {}", wsol_account).cyan().to_string());
-        
-        // Check if WSOL account exists and get balance
-        let (wsol_exists, wsol_balance) = match self.config.app_state.rpc_client.get_account(&wsol_account) {
-            Ok(account) => {
-                match spl_token::state::Account::unpack(&account.data) {
-                    Ok(token_account) => {
-                        let balance = token_account.amount as f64 / 1_000_000_000.0;
-                        self.logger.log(format!("💰 WSOL balance: {} SOL", balance).green().to_string());
-                        (true, balance)
-                    },
-                    Err(_) => {
-                        self.logger.log("❌ WSOL account exists but couldn't parse data".red().to_string());
-                        (false, 0.0)
-                    }
-                }
-            },
-            Err(_) => {
-                self.logger.log("❌ WSOL account doesn't exist".red().to_string());
-                (false, 0.0)
-            }
-        };
-
-        // Get target token balance
-        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
-            .map_err(|e| format!("Invalid target token mint: {}", e))?;
-        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
-        
-        let (target_token_exists, target_token_balance) = match self.config.app_state.rpc_client.get_account(&target_token_account) {
-            Ok(account) => {
-                match spl_token::state::Account::unpack(&account.data) {
-                    Ok(token_account) => {
-                        let balance = token_account.amount;
-                        self.logger.log(format!("🎯 Target token balance: {}", balance).green().to_string());
-                        (true, balance)
-                    },
-                    Err(_) => {
-                        self.logger.log("❌ Target token account exists but couldn't parse data".red().to_string());
-                        (false, 0)
-                    }
-                }
-            },
-            Err(_) => {
-                self.logger.log("❌ Target token account doesn't exist".red().to_string());
-                (false, 0)
-            }
-        };
-
-        // Log all balances
-        self.logger.log(format!("💰 Wallet balances - SOL: {:.6}, WSOL: {:.6}, Token: {}", 
-            sol_balance_f64, wsol_balance, target_token_balance).purple().to_string());
-
-        // Create WSOL account if it doesn't exist
-        if !wsol_exists {
-            self.logger.log("🔧 Creating WSOL account...".yellow().to_string());
-            if let Err(e) = self.create_wsol_account_only(wallet).await {
-                self.logger.log(format!("❌ Failed to create WSOL account: {}", e).red().to_string());
-                return Err(format!("Failed to create WSOL account: {}", e));
-            }
-            self.logger.log("✅ WSOL account created successfully".green().to_string());
-        }
-
-        // Create target token account if it doesn't exist
-        if !target_token_exists {
-            self.logger.log("🔧 Creating target token account...".yellow().to_string());
-            if let Err(e) = self.create_target_token_account(wallet, &target_token_mint).await {
-                self.logger.log(format!("❌ Failed to create target token account: {}", e).red().to_string());
-                return Err(format!("Failed to create target token account: {}", e));
-            }
-            self.logger.log("✅ Target token account created successfully".green().to_string());
-        }
-
-        // Check if we need to wrap SOL to WSOL
-        if wsol_balance < 0.01 && sol_balance_f64 > 0.05 {
-            // Calculate amount to wrap based on user's requirements
-            // If we have SOL balance similar to the user's (0.001205), wrap 85% of it
-            let fee_reserve = 0.0005; // Reserve for transaction fees
-            let available_sol = sol_balance_f64 - fee_reserve;
-            let wrap_amount = if available_sol > 0.001 {
-                available_sol * 0.85 // Wrap 85% of available SOL
-            } else {
-                // Fallback to old logic for very small amounts
-                (sol_balance_f64 - 0.01) * 0.75
-            };
-            
-            if wrap_amount > 0.0005 {
-                self.logger.log(format!("🔄 Wrapping {} SOL to WSOL (85% of available balance)", wrap_amount).yellow().to_string());
-                
-                // Wrap SOL to WSOL
-                if let Err(e) = self.wrap_sol_to_wsol(wallet, wrap_amount).await {
-                    self.logger.log(format!("❌ Failed to wrap SOL to WSOL: {}", e).red().to_string());
-                    return Err(format!("Failed to wrap SOL to WSOL: {}", e));
-                }
-                
-                self.logger.log(format!("✅ Successfully wrapped {} SOL to WSOL", wrap_amount).green().to_string());
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Create WSOL account and wrap SOL
-    async fn create_and_wrap_wsol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        
-        // Create WSOL account instructions
-        let (wsol_account, mut instructions) = token::create_wsol_account(wallet_pubkey)
-            .map_err(|e| format!("Failed to create WSOL account instructions: {}", e))?;
-        
-        // Convert to lamports
-        let lamports = (amount * 1_000_000_000.0) as u64;
-        
-        // Transfer SOL to the WSOL account
-        instructions.push(
-            system_instruction::transfer(
-                &wallet_pubkey,
-                &wsol_account,
-                lamports,
-            )
-        );
-        
-        // Sync native instruction
-        instructions.push(
-            sync_native(&spl_token::id(), &wsol_account)
-                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
-        );
-        
-        // Send transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&wallet_pubkey),
-            &[wallet],
-            recent_blockhash,
-        );
-        
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send WSOL wrap transaction: {}", e))?;
-        
-        self.logger.log(format!("✅ WSOL wrap transaction sent: {}", signature).green().to_string());
-        
-        Ok(())
-    }
-
-    /// Create WSOL account only (without wrapping)
-    async fn create_wsol_account_only(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        
-        // Create WSOL account instructions
-        let (wsol_account, instructions) = token::create_wsol_account(wallet_pubkey)
-            .map_err(|e| format!("Failed to create WSOL account instructions: {}", e))?;
-        
-        // Send transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&wallet_pubkey),
-            &[wallet],
-            recent_blockhash,
-        );
-        
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send WSOL account creation transaction: {}", e))?;
-        
-        self.logger.log(format!("✅ WSOL account created: {} - Signature: {}", wsol_account, signature).green().to_string());
-        
-        Ok(())
-    }
-
-    /// Create target token account
-    async fn create_target_token_account(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, token_mint: &Pubkey) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        
-        // Create associated token account instruction
-        let create_ata_instruction = spl_associated_token_account::instruction::create_associated_token_account(
-            &wallet_pubkey,  // payer
-            &wallet_pubkey,  // owner
-            token_mint,      // mint
-            &spl_token::id(), // token program
-        );
-        
-        // Send transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &[create_ata_instruction],
-            Some(&wallet_pubkey),
-            &[wallet],
-            recent_blockhash,
-        );
-        
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send target token account creation transaction: {}", e))?;
-        
-        let target_token_account = get_associated_token_address(&wallet_pubkey, token_mint);
-        self.logger.log(format!("✅ Target token account created: {} - Signature: {}", target_token_account, signature).green().to_string());
-        
-        Ok(())
-    }
-
-    /// Wrap SOL to WSOL (assuming WSOL account already exists)
-    async fn wrap_sol_to_wsol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        
-        // Convert to lamports
-        let lamports = (amount * 1_000_000_000.0) as u64;
-        
-        let mut instructions = Vec::new();
-        
-        // Transfer SOL to the WSOL account
-        instructions.push(
-            system_instruction::transfer(
-                &wallet_pubkey,
-                &wsol_account,
-                lamports,
-            )
-        );
-        
-        // Sync native instruction
-        instructions.push(
-            sync_native(&spl_token::id(), &wsol_account)
-                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
-        );
-        
-        // Send transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&wallet_pubkey),
-            &[wallet],
-            recent_blockhash,
-        );
-        
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send SOL wrap transaction: {}", e))?;
-        
-        self.logger.log(format!("✅ SOL wrapped to WSOL: {} - Signature: {}", amount, signature).green().to_string());
-        
-        Ok(())
-    }
-
-    /// Unwrap WSOL to SOL (for getting SOL back when needed for fees)
-    async fn unwrap_wsol_to_sol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
-        let wallet_pubkey = wallet.pubkey();
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        
-        // Convert to lamports  
-        let lamports_to_unwrap = (amount * 1_000_000_000.0) as u64;
-        
-        let mut instructions = Vec::new();
-        
-        // Get the minimum balance required for rent exemption of a token account
-        let rent_exempt_lamports = self.config.app_state.rpc_client
-            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
-            .map_err(|e| format!("Failed to get rent exemption amount: {}", e))?;
-        
-        // We need to transfer the unwrap amount + rent exempt amount to create a valid account
-        let total_lamports_needed = lamports_to_unwrap + rent_exempt_lamports;
-        
-        // Create a temporary WSOL account that will be properly funded
-        let temp_account = anchor_client::solana_sdk::signature::Keypair::new();
-        
-        // Create the temporary account with proper rent-exempt amount
-        instructions.push(
-            system_instruction::create_account(
-                &wallet_pubkey,
-                &temp_account.pubkey(),
-                rent_exempt_lamports, // Use rent-exempt amount for account creation
-                spl_token::state::Account::LEN as u64,
-                &spl_token::id(),
-            )
-        );
-        
-        // Initialize the temporary account
-        instructions.push(
-            spl_token::instruction::initialize_account(
-                &spl_token::id(),
-                &temp_account.pubkey(),
-                &spl_token::native_mint::id(),
-                &wallet_pubkey,
-            ).map_err(|e| format!("Failed to create initialize account instruction: {}", e))?
-        );
-        
-        // Transfer WSOL tokens to the temporary account
-        instructions.push(
-            spl_token::instruction::transfer(
-                &spl_token::id(),
-                &wsol_account,
-                &temp_account.pubkey(),
-                &wallet_pubkey,
-                &[&wallet_pubkey],
-                lamports_to_unwrap, // Only transfer the amount we want to unwrap
-            ).map_err(|e| format!("Failed to create transfer instruction: {}", e))?
-        );
-        
-        // Sync native (this converts the transferred WSOL tokens to SOL in the account)
-        instructions.push(
-            sync_native(&spl_token::id(), &temp_account.pubkey())
-                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
-        );
-        
-        // Close the temporary account (this releases ALL SOL to the wallet, including unwrapped amount + rent)
-        instructions.push(
-            spl_token::instruction::close_account(
-                &spl_token::id(),
-                &temp_account.pubkey(),
-                &wallet_pubkey, // destination (where SOL goes)
-                &wallet_pubkey, // owner
-                &[&wallet_pubkey],
-            ).map_err(|e| format!("Failed to create close account instruction: {}", e))?
-        );
-        
-        // Send transaction
-        let recent_blockhash = self.config.app_state.rpc_client.get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&wallet_pubkey),
-            &[wallet, &temp_account],
-            recent_blockhash,
-        );
-        
-        let signature = self.config.app_state.rpc_client.send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send WSOL unwrap transaction: {}", e))?;
-        
-        self.logger.log(format!("✅ WSOL unwrapped to SOL: {:.6} WSOL + rent ({:.6} SOL total) - Signature: {}", 
-            amount, (rent_exempt_lamports as f64 / 1_000_000_000.0), signature).green().to_string());
-        
-        Ok(())
-    }
-
-    /// Process incoming GRPC messages
-    async fn process_grpc_message(&self, msg: &SubscribeUpdate) -> Result<(), String> {
-        if let Some(update_oneof) = &msg.update_oneof {
-            if let UpdateOneof::Transaction(txn_info) = update_oneof {
-                // Parse the transaction for our target token
-                if let Some(trade_info) = parse_target_token_transaction(txn_info, &self.config.target_token_mint) {
-                    self.logger.log(format!(
-                        "🎯 Detected {} trade: User: {}, Volume: {:.6} SOL",
-                        if trade_info.is_buy { "BUY" } else { "SELL" },
-                        trade_info.user,
-                        trade_info.volume_change
-                    ).magenta().to_string());
-                    
-                    // Add to activity tracking for analysis
-                    let activity = TokenActivity {
-                        timestamp: Instant::now(),
-                        is_buy: trade_info.is_buy,
-                        volume_sol: trade_info.volume_change,
-                        user: trade_info.user.clone(),
-                        price: 0.0, // Would need to calculate from pool reserves
-                    };
-                    self.add_token_activity(activity).await;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    /// Send transaction to the network
-    async fn send_transaction(
-        &self,
-        keypair: &Arc<anchor_client::solana_sdk::signature::Keypair>,
-        instructions: Vec<anchor_client::solana_sdk::instruction::Instruction>,
-    ) -> Result<Signature, String> {
-        use anchor_client::solana_sdk::transaction::Transaction;
-        use anchor_client::solana_sdk::signer::Signer;
-
-        // Get recent blockhash
-        let recent_blockhash = self.config.app_state.rpc_client
-            .get_latest_blockhash()
-            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
-
-        // Create and sign transaction
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&keypair.pubkey()),
-            &[keypair.as_ref()],
-            recent_blockhash,
-        );
-
-        // Send transaction
-        let signature = self.config.app_state.rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .map_err(|e| format!("Failed to send transaction: {}", e))?;
-
-        Ok(signature)
-    }
-
-    /// Get trading statistics
-    pub async fn get_trading_stats(&self) -> (u32, usize, HashMap<String, u32>) {
-        let trade_count = *self.trade_counter.lock().await;
-        let wallet_count = {
-            let wallet_pool = self.wallet_pool.lock().await;
-            wallet_pool.wallet_count()
-        };
-        let usage_stats = {
-            let wallet_pool = self.wallet_pool.lock().await;
-            wallet_pool.get_usage_stats()
-        };
-        
-        (trade_count, wallet_count, usage_stats)
-    }
-
-    /// Calculate stealth buy amount based on WSOL balance
-    async fn calculate_stealth_buy_amount(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<f64, String> {
-        let wallet_pubkey = wallet.pubkey();
-        
-        // Get WSOL account address
-        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
-        
-        // Get WSOL balance
-        let wsol_balance = match self.config.app_state.rpc_client.get_account(&wsol_account) {
-            Ok(account) => {
-                match spl_token::state::Account::unpack(&account.data) {
-                    Ok(token_account) => {
-                        token_account.amount as f64 / 1_000_000_000.0
-                    },
-                    Err(_) => 0.0
-                }
-            },
-            Err(_) => 0.0
-        };
-        
-        if wsol_balance < 0.0001 {
-            return Err("Insufficient WSOL balance for stealth buy".to_string());
-        }
-        
-        // Calculate stealth buy amount: wsol_balance * 0.85 * random_range
-        let base_amount = wsol_balance * 0.85;
-        
-        // Apply random multiplier from environment range
-        let min_ratio = self.config.randomization_config.min_amount_sol;
-        let max_ratio = self.config.randomization_config.max_amount_sol;
-        
-        let mut rng = rand::thread_rng();
-        let random_multiplier = min_ratio + (max_ratio - min_ratio) * rng.gen::<f64>();
-        
-        let stealth_amount = base_amount * random_multiplier;
-        
-        // Ensure we don't exceed available WSOL balance
-        let max_safe_amount = wsol_balance * 0.95; // Leave 5% buffer
-        let final_amount = stealth_amount.min(max_safe_amount);
-        
-        self.logger.log(format!("💰 Stealth buy calculation: WSOL: {:.6}, Base: {:.6}, Multiplier: {:.3}, Final: {:.6}", 
-            wsol_balance, base_amount, random_multiplier, final_amount).cyan().to_string());
-        
-        Ok(final_amount)
-    }
-
-    /// Generate token activity analysis report
-    pub async fn generate_activity_report(&self) -> TokenActivityReport {
-        let activities = self.token_activities.lock().await;
-        let now = Instant::now();
-        
-        // Filter activities from the last hour
-        let recent_activities: Vec<_> = activities
-            .iter()
-            .filter(|activity| now.duration_since(activity.timestamp).as_secs() <= 3600)
-            .collect();
-        
-        if recent_activities.is_empty() {
-            return TokenActivityReport {
-                report_period_minutes: 60,
-                ..Default::default()
-            };
-        }
-        
-        let total_trades = recent_activities.len() as u32;
-        let buy_trades = recent_activities.iter().filter(|a| a.is_buy).count() as u32;
-        let sell_trades = total_trades - buy_trades;
-        
-        let total_volume_sol: f64 = recent_activities.iter().map(|a| a.volume_sol).sum();
-        let buy_volume_sol: f64 = recent_activities.iter()
-            .filter(|a| a.is_buy)
-            .map(|a| a.volume_sol)
-            .sum();
-        let sell_volume_sol = total_volume_sol - buy_volume_sol;
-        
-        let prices: Vec<f64> = recent_activities.iter().map(|a| a.price).collect();
-        let average_price = prices.iter().sum::<f64>() / prices.len() as f64;
-        let min_price = prices.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-        let max_price = prices.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        
-        let unique_traders = recent_activities
-            .iter()
-            .map(|a| &a.user)
-            .collect::<std::collections::HashSet<_>>()
-            .len() as u32;
-        
-        TokenActivityReport {
-            total_trades,
-            buy_trades,
-            sell_trades,
-            total_volume_sol,
-            buy_volume_sol,
-            sell_volume_sol,
-            average_price,
-            min_price: if min_price == f64::INFINITY { 0.0 } else { min_price },
-            max_price: if max_price == f64::NEG_INFINITY { 0.0 } else { max_price },
-            unique_traders,
-            report_period_minutes: 60,
-        }
-    }
-    
-    /// Log activity report if enough time has passed
-    pub async fn check_and_log_activity_report(&self) {
-        let now = Instant::now();
-        let should_report = {
-            let mut last_report = self.last_activity_report.lock().await;
-            if now.duration_since(*last_report).as_secs() >= 1800 { // 30 minutes
-                *last_report = now;
-                true
-            } else {
-                false
-            }
-        };
-        
-        if should_report {
-            let report = self.generate_activity_report().await;
-            self.log_activity_report(&report).await;
-        }
-    }
-    
-    /// Log the activity report with detailed statistics
-    pub async fn log_activity_report(&self, report: &TokenActivityReport) {
-        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
-        self.logger.log("📊 TOKEN ACTIVITY ANALYSIS REPORT (Last 60 minutes)".cyan().bold().to_string());
-        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
-        
-        // Trade Statistics
-        self.logger.log(format!("🔢 Total Trades: {}", report.total_trades).green().to_string());
-        self.logger.log(format!("📈 Buy Trades: {} ({:.1}%)", 
-            report.buy_trades, 
-            if report.total_trades > 0 { (report.buy_trades as f64 / report.total_trades as f64) * 100.0 } else { 0.0 }
-        ).green().to_string());
-        self.logger.log(format!("📉 Sell Trades: {} ({:.1}%)", 
-            report.sell_trades,
-            if report.total_trades > 0 { (report.sell_trades as f64 / report.total_trades as f64) * 100.0 } else { 0.0 }
-        ).red().to_string());
-        
-        // Volume Statistics
-        self.logger.log(format!("💰 Total Volume: {:.6} SOL", report.total_volume_sol).cyan().to_string());
-        self.logger.log(format!("💚 Buy Volume: {:.6} SOL ({:.1}%)", 
-            report.buy_volume_sol,
-            if report.total_volume_sol > 0.0 { (report.buy_volume_sol / report.total_volume_sol) * 100.0 } else { 0.0 }
-        ).green().to_string());
-        self.logger.log(format!("💔 Sell Volume: {:.6} SOL ({:.1}%)", 
-            report.sell_volume_sol,
-            if report.total_volume_sol > 0.0 { (report.sell_volume_sol / report.total_volume_sol) * 100.0 } else { 0.0 }
-        ).red().to_string());
-        
-        // Price Statistics
-        self.logger.log(format!("📊 Average Price: ${:.8}", report.average_price).yellow().to_string());
-        self.logger.log(format!("📈 Highest Price: ${:.8}", report.max_price).green().to_string());
-        self.logger.log(format!("📉 Lowest Price: ${:.8}", report.min_price).red().to_string());
-        self.logger.log(format!("💹 Price Range: ${:.8} ({:.2}%)", 
-            report.max_price - report.min_price,
-            if report.min_price > 0.0 { ((report.max_price - report.min_price) / report.min_price) * 100.0 } else { 0.0 }
-        ).magenta().to_string());
-        
-        // Trader Statistics
-        self.logger.log(format!("👥 Unique Traders: {}", report.unique_traders).blue().to_string());
-        self.logger.log(format!("📊 Avg Trades per Trader: {:.1}", 
-            if report.unique_traders > 0 { report.total_trades as f64 / report.unique_traders as f64 } else { 0.0 }
-        ).blue().to_string());
-        
-        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
-    }
-    
-    /// Add a detected token activity for analysis
-    pub async fn add_token_activity(&self, activity: TokenActivity) {
-        let mut activities = self.token_activities.lock().await;
-        activities.push_back(activity.clone());
-        
-        // Keep only last 100 activities to prevent memory issues
-        if activities.len() > 100 {
-            activities.pop_front();
-        }
-        
-        // Add price data to price monitor and guardian mode
-        if activity.price > 0.0 {
-            let mut price_monitor = self.price_monitor.lock().await;
-            price_monitor.add_price_point(activity.price, activity.volume_sol);
-            
-            let mut guardian_mode = self.guardian_mode.lock().await;
-            guardian_mode.add_price_point(activity.price, activity.volume_sol);
-        }
-    }
-}
-
-/// Helper to send heartbeat pings to maintain GRPC connection
-async fn send_heartbeat_ping(
-    subscribe_tx: &Arc<tokio::sync::Mutex<impl Sink<SubscribeRequest, Error = impl std::fmt::Debug> + Unpin>>,
-) -> Result<(), String> {
-    let ping_request = SubscribeRequest {
-        ping: Some(SubscribeRequestPing { id: 0 }),
-        ..Default::default()
-    };
-    
-    let mut tx = subscribe_tx.lock().await;
-    match tx.send(ping_request).await {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!("Failed to send ping: {:?}", e)),
-    }
-}
-
-/// Start advanced market maker with configuration
-pub async fn start_market_maker(config: MarketMakerConfig) -> Result<(), String> {
-    let market_maker = MarketMaker::new(config)?;
-    market_maker.start().await
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, VecDeque};
+use tokio::time::Instant;
+use anyhow::Result;
+use anchor_client::solana_sdk::signature::Signature;
+use anchor_client::solana_sdk::signer::Signer;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::system_instruction;
+use anchor_client::solana_sdk::transaction::Transaction;
+use colored::Colorize;
+use tokio::time;
+use tokio::sync::Mutex;
+use futures_util::stream::{StreamExt, FuturesUnordered};
+use futures_util::{SinkExt, Sink};
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+use yellowstone_grpc_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestPing,
+    SubscribeRequestFilterTransactions, SubscribeUpdate,
+};
+use crate::engine::transaction_parser;
+use crate::common::{
+    config::{AppState, SwapConfig, JUPITER_PROGRAM, OKX_DEX_PROGRAM},
+    logger::Logger,
+    wallet_pool::{WalletPool, RandomizationConfig, TradeType},
+    price_monitor::{GlobalPriceMonitor, create_global_price_monitor},
+    dynamic_ratios::{GlobalDynamicRatioManager, create_global_dynamic_ratio_manager},
+    volume_waves::{GlobalVolumeWaveManager, create_global_volume_wave_manager},
+    guardian_mode::{GlobalGuardianMode, create_global_guardian_mode},
+};
+use crate::dex::raydium_cpmm::RaydiumCPMM;
+use crate::engine::swap::{SwapDirection, SwapInType};
+use crate::core::token;
+use spl_token::instruction::sync_native;
+use spl_associated_token_account::get_associated_token_address;
+use solana_program_pack::Pack;
+use std::str::FromStr;
+use rand::Rng;
+use crate::engine::transaction_parser::{parse_target_token_transaction, TradeInfoFromToken};
+use tracing::{info, warn, error, instrument};
+
+pub mod metrics {
+    //! Prometheus metrics for the market maker, scraped over HTTP so operators
+    //! can alert on failure spikes or stalled trading intervals instead of
+    //! grepping colored stdout logs.
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use prometheus::{
+        Counter, Encoder, Gauge, IntCounter, IntGauge, Registry, TextEncoder,
+    };
+
+    pub struct TradeMetrics {
+        pub registry: Registry,
+        pub trades_total: IntCounter,
+        pub buy_volume_sol: Counter,
+        pub sell_volume_sol: Counter,
+        pub failed_transactions: IntCounter,
+        pub active_wallets: IntGauge,
+        pub dynamic_buy_ratio: Gauge,
+        pub guardian_mode_active: IntGauge,
+        pub tpu_transactions_per_second: Gauge,
+    }
+
+    impl TradeMetrics {
+        pub fn new() -> Result<Self, prometheus::Error> {
+            let registry = Registry::new();
+
+            let trades_total = IntCounter::new("mm_trades_total", "Total executed trades")?;
+            let buy_volume_sol = Counter::new("mm_buy_volume_sol", "Cumulative buy volume in SOL")?;
+            let sell_volume_sol = Counter::new("mm_sell_volume_sol", "Cumulative sell volume in SOL")?;
+            let failed_transactions = IntCounter::new("mm_failed_transactions_total", "Total failed trade transactions")?;
+            let active_wallets = IntGauge::new("mm_active_wallets", "Number of wallets in the rotation pool")?;
+            let dynamic_buy_ratio = Gauge::new("mm_dynamic_buy_ratio", "Current dynamic buy ratio (0-1)")?;
+            let guardian_mode_active = IntGauge::new("mm_guardian_mode_active", "1 if guardian mode is currently suppressing sells")?;
+            let tpu_transactions_per_second = Gauge::new("mm_tpu_transactions_per_second", "Rolling TPU send rate over the trailing window")?;
+
+            registry.register(Box::new(trades_total.clone()))?;
+            registry.register(Box::new(buy_volume_sol.clone()))?;
+            registry.register(Box::new(sell_volume_sol.clone()))?;
+            registry.register(Box::new(failed_transactions.clone()))?;
+            registry.register(Box::new(active_wallets.clone()))?;
+            registry.register(Box::new(dynamic_buy_ratio.clone()))?;
+            registry.register(Box::new(guardian_mode_active.clone()))?;
+            registry.register(Box::new(tpu_transactions_per_second.clone()))?;
+
+            Ok(Self {
+                registry,
+                trades_total,
+                buy_volume_sol,
+                sell_volume_sol,
+                failed_transactions,
+                active_wallets,
+                dynamic_buy_ratio,
+                guardian_mode_active,
+                tpu_transactions_per_second,
+            })
+        }
+
+        fn render(&self) -> Vec<u8> {
+            let encoder = TextEncoder::new();
+            let metric_families = self.registry.gather();
+            let mut buffer = Vec::new();
+            encoder.encode(&metric_families, &mut buffer).ok();
+            buffer
+        }
+    }
+
+    /// Serve `/metrics` on `addr` until the process exits.
+    pub async fn serve_metrics(metrics: Arc<TradeMetrics>, addr: SocketAddr) -> Result<(), hyper::Error> {
+        let make_svc = make_service_fn(move |_conn| {
+            let metrics = metrics.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+                    let metrics = metrics.clone();
+                    async move {
+                        let body = if req.uri().path() == "/metrics" {
+                            metrics.render()
+                        } else {
+                            b"not found".to_vec()
+                        };
+                        Ok::<_, hyper::Error>(Response::new(Body::from(body)))
+                    }
+                }))
+            }
+        });
+
+        Server::bind(&addr).serve(make_svc).await
+    }
+}
+
+/// JSON HTTP API exposing the same ticker/trades/candles state that
+/// otherwise only reaches an operator through `log_activity_report`'s
+/// colored stdout, so dashboards and alerting can poll it directly. Built
+/// on the same `hyper` primitives as `metrics::serve_metrics` rather than
+/// pulling in a routing framework for three endpoints.
+pub mod data_api {
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Method, Request, Response, Server, StatusCode};
+    use serde::Serialize;
+    use super::candles::Interval;
+    use super::MarketMaker;
+
+    #[derive(Serialize)]
+    struct TickerResponse {
+        current_price: f64,
+        average_price: f64,
+        high_price: f64,
+        low_price: f64,
+        buy_volume_sol: f64,
+        sell_volume_sol: f64,
+        window_minutes: u64,
+        /// True if a hot trader's per-shard history cap evicted data still
+        /// inside the report window, so the figures above may undercount.
+        truncated: bool,
+    }
+
+    #[derive(Serialize)]
+    struct TradeEntry {
+        is_buy: bool,
+        volume_sol: f64,
+        price: f64,
+        user: String,
+        signature: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct CandleEntry {
+        interval_start: i64,
+        open: f64,
+        /// `null` when no priced sample landed in this bucket.
+        high: Option<f64>,
+        low: Option<f64>,
+        close: f64,
+        volume_sol: f64,
+        trade_count: u32,
+        gap: bool,
+    }
+
+    /// Splits a request's raw query string into `key=value` pairs. No
+    /// percent-decoding - `limit` and `interval` are the only params this
+    /// API reads and neither ever needs it.
+    fn parse_query(query: Option<&str>) -> HashMap<String, String> {
+        query
+            .unwrap_or("")
+            .split('&')
+            .filter_map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let key = parts.next()?.to_string();
+                if key.is_empty() {
+                    return None;
+                }
+                Some((key, parts.next().unwrap_or("").to_string()))
+            })
+            .collect()
+    }
+
+    fn json_response(body: &impl Serialize) -> Response<Body> {
+        match serde_json::to_vec(body) {
+            Ok(bytes) => Response::builder()
+                .header("content-type", "application/json")
+                .body(Body::from(bytes))
+                .unwrap(),
+            Err(_) => Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("failed to encode response"))
+                .unwrap(),
+        }
+    }
+
+    fn plain_error(status: StatusCode, message: &'static str) -> Response<Body> {
+        Response::builder().status(status).body(Body::from(message)).unwrap()
+    }
+
+    async fn handle(market_maker: Arc<MarketMaker>, req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+        if req.method() != Method::GET {
+            return Ok(plain_error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported"));
+        }
+
+        let params = parse_query(req.uri().query());
+
+        let response = match req.uri().path() {
+            "/ticker" => {
+                let report = market_maker.generate_activity_report().await;
+                let current_price = market_maker.current_price().await.unwrap_or(report.average_price);
+                json_response(&TickerResponse {
+                    current_price,
+                    average_price: report.average_price,
+                    high_price: report.max_price,
+                    low_price: report.min_price,
+                    buy_volume_sol: report.buy_volume_sol,
+                    sell_volume_sol: report.sell_volume_sol,
+                    window_minutes: report.report_period_minutes,
+                    truncated: report.truncated,
+                })
+            }
+            "/trades" => {
+                let limit = params.get("limit").and_then(|v| v.parse().ok()).unwrap_or(50usize);
+                let trades: Vec<TradeEntry> = market_maker
+                    .get_recent_activities(limit)
+                    .await
+                    .into_iter()
+                    .map(|a| TradeEntry {
+                        is_buy: a.is_buy,
+                        volume_sol: a.volume_sol,
+                        price: a.price,
+                        user: a.user,
+                        signature: a.signature,
+                    })
+                    .collect();
+                json_response(&trades)
+            }
+            "/candles" => {
+                let interval = match params.get("interval").map(String::as_str) {
+                    Some("1m") | None => Interval::OneMinute,
+                    Some("5m") => Interval::FiveMinutes,
+                    Some("1h") => Interval::OneHour,
+                    _ => return Ok(plain_error(StatusCode::BAD_REQUEST, "invalid interval, expected 1m, 5m, or 1h")),
+                };
+                let limit = params.get("limit").and_then(|v| v.parse().ok()).unwrap_or(100usize);
+                let candles: Vec<CandleEntry> = market_maker
+                    .get_candles(interval, limit)
+                    .await
+                    .into_iter()
+                    .map(|c| CandleEntry {
+                        interval_start: c.interval_start,
+                        open: c.open,
+                        high: c.high,
+                        low: c.low,
+                        close: c.close,
+                        volume_sol: c.volume_sol,
+                        trade_count: c.trade_count,
+                        gap: c.gap,
+                    })
+                    .collect();
+                json_response(&candles)
+            }
+            _ => plain_error(StatusCode::NOT_FOUND, "not found"),
+        };
+
+        Ok(response)
+    }
+
+    /// Serve `/ticker`, `/trades`, and `/candles` on `addr` until the
+    /// process exits.
+    pub async fn serve(market_maker: Arc<MarketMaker>, addr: SocketAddr) -> Result<(), hyper::Error> {
+        let make_svc = make_service_fn(move |_conn| {
+            let market_maker = market_maker.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req| handle(market_maker.clone(), req)))
+            }
+        });
+
+        Server::bind(&addr).serve(make_svc).await
+    }
+}
+
+pub mod jupiter_route {
+    //! Jupiter v6 quote + swap-instruction fetching, used as an alternative
+    //! to the direct Raydium path when it quotes a better price. Every
+    //! call is bounded by a hard timeout so a slow aggregator response never
+    //! blocks the main trading loop.
+    use std::time::Duration;
+    use anchor_client::solana_sdk::instruction::{AccountMeta, Instruction};
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use base64::Engine;
+    use serde::{Deserialize, Serialize};
+    use std::str::FromStr;
+
+    const QUOTE_URL: &str = "https://quote-api.jup.ag/v6/quote";
+    const SWAP_INSTRUCTIONS_URL: &str = "https://quote-api.jup.ag/v6/swap-instructions";
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct QuoteResponse {
+        #[serde(rename = "outAmount")]
+        out_amount: String,
+        #[serde(flatten)]
+        rest: std::collections::HashMap<String, serde_json::Value>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct InstructionAccount {
+        pubkey: String,
+        #[serde(rename = "isSigner")]
+        is_signer: bool,
+        #[serde(rename = "isWritable")]
+        is_writable: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct InstructionResponse {
+        #[serde(rename = "programId")]
+        program_id: String,
+        accounts: Vec<InstructionAccount>,
+        data: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct SwapInstructionsResponse {
+        #[serde(rename = "swapInstruction")]
+        swap_instruction: InstructionResponse,
+    }
+
+    pub struct JupiterRoute {
+        pub instructions: Vec<Instruction>,
+        pub out_amount: u64,
+    }
+
+    fn to_instruction(resp: &InstructionResponse) -> Result<Instruction, String> {
+        let program_id = Pubkey::from_str(&resp.program_id).map_err(|e| e.to_string())?;
+        let accounts = resp
+            .accounts
+            .iter()
+            .map(|a| -> Result<AccountMeta, String> {
+                let pubkey = Pubkey::from_str(&a.pubkey).map_err(|e| e.to_string())?;
+                Ok(if a.is_writable {
+                    AccountMeta::new(pubkey, a.is_signer)
+                } else {
+                    AccountMeta::new_readonly(pubkey, a.is_signer)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&resp.data)
+            .map_err(|e| e.to_string())?;
+        Ok(Instruction { program_id, accounts, data })
+    }
+
+    /// Fetch a Jupiter v6 quote and the matching swap instruction, bounded by
+    /// `timeout`. Returns `None` on timeout or any request/parse failure so
+    /// callers can silently fall back to the direct Raydium path.
+    pub async fn fetch_route(
+        http: &reqwest::Client,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount_lamports: u64,
+        slippage_bps: u64,
+        user: &Pubkey,
+        timeout: Duration,
+    ) -> Option<JupiterRoute> {
+        tokio::time::timeout(timeout, async {
+            let quote: QuoteResponse = http
+                .get(QUOTE_URL)
+                .query(&[
+                    ("inputMint", input_mint.to_string()),
+                    ("outputMint", output_mint.to_string()),
+                    ("amount", amount_lamports.to_string()),
+                    ("slippageBps", slippage_bps.to_string()),
+                ])
+                .send()
+                .await
+                .ok()?
+                .json()
+                .await
+                .ok()?;
+            let out_amount: u64 = quote.out_amount.parse().ok()?;
+
+            let swap_instructions: SwapInstructionsResponse = http
+                .post(SWAP_INSTRUCTIONS_URL)
+                .json(&serde_json::json!({
+                    "quoteResponse": quote,
+                    "userPublicKey": user.to_string(),
+                }))
+                .send()
+                .await
+                .ok()?
+                .json()
+                .await
+                .ok()?;
+
+            let instruction = to_instruction(&swap_instructions.swap_instruction).ok()?;
+            Some(JupiterRoute { instructions: vec![instruction], out_amount })
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+/// Direct-to-leader transaction submission over TPU, bypassing the single
+/// RPC node that `new_signed_and_send_skip_simulation_force` and
+/// `send_and_confirm_transaction` both go through. Fanning an
+/// already-signed transaction out to the next few leaders' TPU ports skips
+/// the RPC relay hop entirely, which is where most of the landing latency
+/// during a volume burst comes from.
+pub mod tpu {
+    use std::collections::{HashMap, VecDeque};
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+    use tokio::time::Instant;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use anchor_client::solana_sdk::signature::Signature;
+    use anchor_client::solana_sdk::transaction::Transaction;
+    use crate::common::config::AppState;
+
+    /// Bookkeeping for one transaction sent over TPU, kept around until its
+    /// blockhash expires so callers know when it's safe to stop retrying.
+    #[derive(Debug, Clone)]
+    pub struct SentTransactionInfo {
+        pub signature: Signature,
+        pub last_valid_block_height: u64,
+        pub sent_at: Instant,
+    }
+
+    /// Rolling transactions-per-second counter over a fixed window, reported
+    /// alongside the existing `trade_counter`. It counts TPU sends as they
+    /// go out; whether a send later confirms on chain is not tracked here.
+    struct TpsWindow {
+        window: Duration,
+        sent_at: VecDeque<Instant>,
+    }
+
+    impl TpsWindow {
+        fn new(window: Duration) -> Self {
+            Self { window, sent_at: VecDeque::new() }
+        }
+
+        fn record(&mut self) {
+            let now = Instant::now();
+            self.sent_at.push_back(now);
+            while let Some(front) = self.sent_at.front() {
+                if now.duration_since(*front) > self.window {
+                    self.sent_at.pop_front();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        fn tps(&self) -> f64 {
+            let now = Instant::now();
+            let recent = self.sent_at.iter().filter(|t| now.duration_since(**t) <= self.window).count();
+            recent as f64 / self.window.as_secs_f64()
+        }
+    }
+
+    /// Sends already-signed transactions straight to the TPU ports of the
+    /// upcoming leaders, once per call; `MarketMaker::send_via_tpu` re-signs
+    /// against a fresh blockhash and calls it again when a send does not
+    /// confirm.
+    pub struct TpuSubmitter {
+        socket: UdpSocket,
+        leader_tpu_by_pubkey: Mutex<HashMap<Pubkey, SocketAddr>>,
+        in_flight: Mutex<Vec<SentTransactionInfo>>,
+        tps_window: Mutex<TpsWindow>,
+    }
+
+    impl TpuSubmitter {
+        pub fn new() -> std::io::Result<Self> {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            socket.set_nonblocking(true)?;
+            Ok(Self {
+                socket,
+                leader_tpu_by_pubkey: Mutex::new(HashMap::new()),
+                in_flight: Mutex::new(Vec::new()),
+                tps_window: Mutex::new(TpsWindow::new(Duration::from_secs(10))),
+            })
+        }
+
+        /// Rebuilds the pubkey -> TPU-socket map from `getClusterNodes`.
+        /// Cheap enough to call before each leader lookup.
+        async fn refresh_cluster_nodes(&self, app_state: &Arc<AppState>) -> Result<(), String> {
+            let nodes = app_state
+                .rpc_client
+                .get_cluster_nodes()
+                .map_err(|e| format!("Failed to fetch cluster nodes: {}", e))?;
+
+            let mut map = self.leader_tpu_by_pubkey.lock().await;
+            map.clear();
+            for node in nodes {
+                if let (Ok(pubkey), Some(tpu)) = (node.pubkey.parse::<Pubkey>(), node.tpu) {
+                    map.insert(pubkey, tpu);
+                }
+            }
+            Ok(())
+        }
+
+        /// Resolves the TPU sockets of the leaders of the next `lookahead`
+        /// slots starting at the current slot.
+        async fn next_leader_sockets(
+            &self,
+            app_state: &Arc<AppState>,
+            lookahead: u64,
+        ) -> Result<Vec<SocketAddr>, String> {
+            self.refresh_cluster_nodes(app_state).await?;
+
+            let epoch_info = app_state
+                .rpc_client
+                .get_epoch_info()
+                .map_err(|e| format!("Failed to fetch epoch info: {}", e))?;
+            let current_slot = epoch_info.absolute_slot;
+            // Leader schedule slot indices are relative to the epoch's first slot.
+            let epoch_start_slot = epoch_info.absolute_slot - epoch_info.slot_index;
+
+            let leader_schedule = app_state
+                .rpc_client
+                .get_leader_schedule(Some(current_slot))
+                .map_err(|e| format!("Failed to fetch leader schedule: {}", e))?
+                .ok_or_else(|| "No leader schedule returned for current epoch".to_string())?;
+
+            let map = self.leader_tpu_by_pubkey.lock().await;
+            let mut sockets = Vec::new();
+            for (pubkey_str, slots) in leader_schedule {
+                let Ok(pubkey) = pubkey_str.parse::<Pubkey>() else { continue };
+                let Some(tpu) = map.get(&pubkey) else { continue };
+                if slots.iter().any(|slot| {
+                    let absolute = epoch_start_slot + *slot as u64;
+                    absolute >= current_slot && absolute < current_slot + lookahead
+                }) {
+                    sockets.push(*tpu);
+                }
+            }
+            Ok(sockets)
+        }
+
+        /// Fans a serialized, already-signed transaction out to the upcoming
+        /// leaders' TPU ports and records it for TPS accounting.
+        pub async fn send_transaction(
+            &self,
+            app_state: &Arc<AppState>,
+            transaction: &Transaction,
+            last_valid_block_height: u64,
+        ) -> Result<SentTransactionInfo, String> {
+            let wire = bincode::serialize(transaction)
+                .map_err(|e| format!("Failed to serialize transaction for TPU send: {}", e))?;
+
+            let leaders = self.next_leader_sockets(app_state, 4).await?;
+            if leaders.is_empty() {
+                return Err("No upcoming leader TPU sockets resolved".to_string());
+            }
+
+            for leader in &leaders {
+                // Best-effort fan-out: a dropped UDP datagram to one leader
+                // shouldn't fail the whole send, only RPC-side confirmation
+                // of the signature matters in the end.
+                let _ = self.socket.send_to(&wire, leader);
+            }
+
+            self.tps_window.lock().await.record();
+
+            let info = SentTransactionInfo {
+                signature: transaction.signatures[0],
+                last_valid_block_height,
+                sent_at: Instant::now(),
+            };
+            self.in_flight.lock().await.push(info.clone());
+            Ok(info)
+        }
+
+        /// Drops in-flight entries whose blockhash has since expired so the
+        /// tracking list doesn't grow unbounded.
+        pub async fn sweep_expired(&self, current_block_height: u64) {
+            let mut in_flight = self.in_flight.lock().await;
+            in_flight.retain(|entry| entry.last_valid_block_height >= current_block_height);
+        }
+
+        /// Rolling transactions-per-second over the trailing window.
+        pub async fn transactions_per_second(&self) -> f64 {
+            self.tps_window.lock().await.tps()
+        }
+    }
+}
+
+/// Dynamic compute-unit pricing so swaps bid a realistic fee instead of
+/// landing at base price and getting skipped during volume bursts.
+pub mod priority_fee {
+    use std::sync::Arc;
+    use anchor_client::solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use anchor_client::solana_sdk::instruction::Instruction;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use tokio::sync::Mutex;
+    use crate::common::config::AppState;
+
+    /// Tracks a smoothed compute-unit price so back-to-back trades don't
+    /// spike the fee every time congestion blips, while still reacting to a
+    /// sustained change within a few trades.
+    pub struct PriorityFeeEstimator {
+        /// Percentile of the returned per-slot fees to bid, e.g. 0.75 for p75.
+        percentile: f64,
+        min_micro_lamports: u64,
+        max_micro_lamports: u64,
+        /// EWMA smoothing factor in (0, 1]; higher weighs the latest sample more.
+        smoothing: f64,
+        ewma: Mutex<Option<f64>>,
+    }
+
+    impl PriorityFeeEstimator {
+        pub fn new(percentile: f64, min_micro_lamports: u64, max_micro_lamports: u64, smoothing: f64) -> Self {
+            Self {
+                percentile,
+                min_micro_lamports,
+                max_micro_lamports,
+                smoothing,
+                ewma: Mutex::new(None),
+            }
+        }
+
+        /// Fetches recent prioritization fees for the given write-locked
+        /// accounts, takes the configured percentile, folds it into the
+        /// running EWMA, and clamps the result to `[min, max]`
+        /// micro-lamports per compute unit.
+        pub async fn estimate_unit_price(
+            &self,
+            app_state: &Arc<AppState>,
+            write_locked_accounts: &[Pubkey],
+        ) -> u64 {
+            let sample = app_state
+                .rpc_client
+                .get_recent_prioritization_fees(write_locked_accounts)
+                .ok()
+                .and_then(|mut fees| {
+                    if fees.is_empty() {
+                        return None;
+                    }
+                    fees.sort_by_key(|f| f.prioritization_fee);
+                    let index = ((fees.len() - 1) as f64 * self.percentile).round() as usize;
+                    Some(fees[index.min(fees.len() - 1)].prioritization_fee as f64)
+                });
+
+            let mut ewma = self.ewma.lock().await;
+            let smoothed = match (sample, *ewma) {
+                (Some(sample), Some(previous)) => previous + self.smoothing * (sample - previous),
+                (Some(sample), None) => sample,
+                (None, Some(previous)) => previous,
+                (None, None) => self.min_micro_lamports as f64,
+            };
+            *ewma = Some(smoothed);
+
+            (smoothed.round() as u64).clamp(self.min_micro_lamports, self.max_micro_lamports)
+        }
+
+        /// Builds the `ComputeBudgetInstruction` pair meant to be prepended
+        /// to a transaction's instruction list: a unit price bid sized from
+        /// recent network congestion, and a unit limit sized to the
+        /// instruction count that will follow it.
+        pub fn build_instructions(unit_price_micro_lamports: u64, instruction_count: usize) -> Vec<Instruction> {
+            // Budget per instruction generously (CPI-heavy swap instructions
+            // routinely exceed the default 200k limit) and floor it so a
+            // single-instruction transfer doesn't request an absurd limit.
+            let unit_limit = ((instruction_count.max(1) as u32) * 120_000).clamp(200_000, 1_400_000);
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_price(unit_price_micro_lamports),
+                ComputeBudgetInstruction::set_compute_unit_limit(unit_limit),
+            ]
+        }
+    }
+}
+
+/// Raydium's concentrated-liquidity (CLMM) swap path, mirroring the
+/// `new` / `build_swap_from_default_info` surface of `RaydiumCPMM` so the
+/// rest of the trading path can stay pool-kind-agnostic. Tick-array
+/// derivation and sqrt-price math follow Raydium's public CLMM layout.
+pub mod raydium_clmm {
+    use std::sync::Arc;
+    use std::str::FromStr;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use anchor_client::solana_sdk::signature::Keypair;
+    use anchor_client::solana_sdk::signer::Signer;
+    use anchor_client::solana_sdk::instruction::{AccountMeta, Instruction};
+    use anchor_client::solana_client::rpc_client::RpcClient;
+    use anchor_client::solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+    use spl_associated_token_account::get_associated_token_address;
+    use crate::common::config::SwapConfig;
+
+    pub const CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+    pub const CPMM_PROGRAM_ID: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
+
+    /// Ticks per array, fixed by the CLMM program.
+    const TICK_ARRAY_SIZE: i32 = 60;
+
+    /// Derives the tick-array PDA that contains `tick_index` for `pool`,
+    /// following Raydium's `["tick_array", pool, start_index_be_bytes]` seeds.
+    fn tick_array_address(pool: &Pubkey, tick_index: i32, tick_spacing: u16) -> (Pubkey, i32) {
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let start_index = tick_index.div_euclid(ticks_in_array) * ticks_in_array;
+        let program_id = Pubkey::from_str(CLMM_PROGRAM_ID).expect("static CLMM program id");
+        let (address, _bump) = Pubkey::find_program_address(
+            &[b"tick_array", pool.as_ref(), &start_index.to_be_bytes()],
+            &program_id,
+        );
+        (address, start_index)
+    }
+
+    /// Converts a Q64.64 sqrt-price (the on-chain representation CLMM pools
+    /// store) into a human `token1 per token0` price. `pub(super)` so
+    /// `pool_price` can reuse it for out-of-band reads that don't go
+    /// through `build_swap_from_default_info`.
+    pub(super) fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_0: u8, decimals_1: u8) -> f64 {
+        let sqrt_price = sqrt_price_x64 as f64 / (1u128 << 64) as f64;
+        let raw_price = sqrt_price * sqrt_price;
+        raw_price * 10f64.powi(decimals_0 as i32 - decimals_1 as i32)
+    }
+
+    /// Concentrated-liquidity counterpart to `RaydiumCPMM`. Like the CPMM
+    /// client, a fresh instance is created per trade with the wallet that
+    /// will sign it; pool discovery and instruction assembly happen inside
+    /// `build_swap_from_default_info`.
+    pub struct RaydiumCLMM {
+        wallet: Arc<Keypair>,
+        rpc_client: Option<Arc<RpcClient>>,
+        rpc_nonblocking_client: Option<Arc<NonblockingRpcClient>>,
+    }
+
+    impl RaydiumCLMM {
+        pub fn new(
+            wallet: Arc<Keypair>,
+            rpc_client: Option<Arc<RpcClient>>,
+            rpc_nonblocking_client: Option<Arc<NonblockingRpcClient>>,
+        ) -> Self {
+            Self { wallet, rpc_client, rpc_nonblocking_client }
+        }
+
+        /// Builds the swap instruction set for a CLMM pool, returning the
+        /// same `(fee payer, instructions, price)` triple as
+        /// `RaydiumCPMM::build_swap_from_default_info` so call sites don't
+        /// need to know which venue they're trading on.
+        pub async fn build_swap_from_default_info(
+            &self,
+            swap_config: SwapConfig,
+        ) -> Result<(Arc<Keypair>, Vec<Instruction>, f64), String> {
+            let rpc_client = self.rpc_client.as_ref()
+                .ok_or_else(|| "RaydiumCLMM requires an rpc_client".to_string())?;
+
+            let target_mint = Pubkey::from_str(&swap_config.mint)
+                .map_err(|e| format!("Invalid target mint: {}", e))?;
+            let wsol_mint = spl_token::native_mint::id();
+            let program_id = Pubkey::from_str(CLMM_PROGRAM_ID).expect("static CLMM program id");
+
+            let (pool_state, _bump) = Pubkey::find_program_address(
+                &[b"pool", wsol_mint.as_ref(), target_mint.as_ref()],
+                &program_id,
+            );
+
+            let pool_account = rpc_client.get_account(&pool_state)
+                .map_err(|e| format!("Failed to fetch CLMM pool state {}: {}", pool_state, e))?;
+
+            // Layout (public Raydium CLMM IDL): discriminator(8) + bump(1) +
+            // amm_config(32) + owner(32) + token_mint_0(32) + token_mint_1(32)
+            // + token_vault_0(32) + token_vault_1(32) + observation(32) +
+            // mint_decimals_0(1) + mint_decimals_1(1) + tick_spacing(2) +
+            // liquidity(16) + sqrt_price_x64(16) + tick_current(4) ...
+            let data = &pool_account.data;
+            if data.len() < 8 + 1 + 32 * 7 + 1 + 1 + 2 + 16 + 16 + 4 {
+                return Err("CLMM pool account data too short to decode".to_string());
+            }
+            let mut offset = 8 + 1 + 32; // skip discriminator, bump, amm_config
+            let owner = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad owner".to_string())?;
+            offset += 32;
+            let token_mint_0 = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad mint0".to_string())?;
+            offset += 32;
+            let token_mint_1 = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad mint1".to_string())?;
+            offset += 32;
+            let token_vault_0 = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad vault0".to_string())?;
+            offset += 32;
+            let token_vault_1 = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad vault1".to_string())?;
+            offset += 32;
+            let observation_state = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad observation".to_string())?;
+            offset += 32;
+            let decimals_0 = data[offset];
+            let decimals_1 = data[offset + 1];
+            offset += 2;
+            let tick_spacing = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            offset += 2;
+            offset += 16; // liquidity
+            let sqrt_price_x64 = u128::from_le_bytes(data[offset..offset + 16].try_into().map_err(|_| "bad sqrt_price".to_string())?);
+            offset += 16;
+            let tick_current = i32::from_le_bytes(data[offset..offset + 4].try_into().map_err(|_| "bad tick_current".to_string())?);
+
+            let zero_for_one = token_mint_0 == wsol_mint;
+            let price = {
+                let raw = sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1);
+                // `raw` is token1 per token0; quote the target token in SOL.
+                if !zero_for_one { raw } else if raw > 0.0 { 1.0 / raw } else { 0.0 }
+            };
+
+            let (tick_array_0, _) = tick_array_address(&pool_state, tick_current, tick_spacing);
+            let (tick_array_1, _) = tick_array_address(
+                &pool_state,
+                if zero_for_one { tick_current - TICK_ARRAY_SIZE * tick_spacing as i32 } else { tick_current + TICK_ARRAY_SIZE * tick_spacing as i32 },
+                tick_spacing,
+            );
+
+            let amount_in_lamports = (swap_config.amount_in * 1_000_000_000.0) as u64;
+            let input_token_account = get_associated_token_address(&self.wallet.pubkey(), &wsol_mint);
+            let output_token_account = get_associated_token_address(&self.wallet.pubkey(), &target_mint);
+            let (input_vault, output_vault) = if zero_for_one {
+                (token_vault_0, token_vault_1)
+            } else {
+                (token_vault_1, token_vault_0)
+            };
+
+            // `swap_v2` discriminator + borsh-packed args, matching Raydium's
+            // published CLMM IDL (amount, other_amount_threshold, sqrt price
+            // limit of 0 meaning "no limit", is_base_input).
+            let mut data = vec![0x2b, 0x04, 0xed, 0x0b, 0x1a, 0xc9, 0x1e, 0x62];
+            data.extend_from_slice(&amount_in_lamports.to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes()); // other_amount_threshold: 0, so no minimum output; swap_config.slippage is not applied on this path
+            data.extend_from_slice(&0u128.to_le_bytes()); // sqrt_price_limit_x64: no limit
+            data.push(1); // is_base_input
+
+            let swap_instruction = Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new(self.wallet.pubkey(), true),
+                    AccountMeta::new_readonly(owner, false),
+                    AccountMeta::new(pool_state, false),
+                    AccountMeta::new(input_token_account, false),
+                    AccountMeta::new(output_token_account, false),
+                    AccountMeta::new(input_vault, false),
+                    AccountMeta::new(output_vault, false),
+                    AccountMeta::new(observation_state, false),
+                    AccountMeta::new_readonly(spl_token::id(), false),
+                    AccountMeta::new(tick_array_0, false),
+                    AccountMeta::new(tick_array_1, false),
+                ],
+                data,
+            };
+
+            Ok((self.wallet.clone(), vec![swap_instruction], price))
+        }
+    }
+
+    /// Which Raydium program owns the target pool, detected once at
+    /// startup so the bot trades on whichever venue the token actually
+    /// lists on without needing a config flag.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PoolKind {
+        Cpmm,
+        Clmm,
+    }
+
+    /// Probes both the CPMM and CLMM pool PDAs for `target_mint` against
+    /// SOL and returns whichever one actually exists on-chain, defaulting
+    /// to CPMM (the bot's original, only, venue) if neither can be found
+    /// so startup never hard-fails over this.
+    pub fn detect_pool_kind(rpc_client: &RpcClient, target_mint: &Pubkey) -> PoolKind {
+        let wsol_mint = spl_token::native_mint::id();
+
+        if let Ok(cpmm_program) = Pubkey::from_str(CPMM_PROGRAM_ID) {
+            let (cpmm_pool, _) = Pubkey::find_program_address(
+                &[b"pool", wsol_mint.as_ref(), target_mint.as_ref()],
+                &cpmm_program,
+            );
+            if rpc_client.get_account(&cpmm_pool).is_ok() {
+                return PoolKind::Cpmm;
+            }
+        }
+
+        if let Ok(clmm_program) = Pubkey::from_str(CLMM_PROGRAM_ID) {
+            let (clmm_pool, _) = Pubkey::find_program_address(
+                &[b"pool", wsol_mint.as_ref(), target_mint.as_ref()],
+                &clmm_program,
+            );
+            if rpc_client.get_account(&clmm_pool).is_ok() {
+                return PoolKind::Clmm;
+            }
+        }
+
+        PoolKind::Cpmm
+    }
+}
+
+/// Background-refreshed blockhash cache so signing a transaction doesn't
+/// pay a synchronous RPC round-trip (and a hard failure point) on every
+/// single buy/sell.
+pub mod blockhash_provider {
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tokio::time::{sleep, Instant};
+    use anchor_client::solana_sdk::hash::Hash;
+    use crate::common::config::AppState;
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct CachedBlockhash {
+        pub blockhash: Hash,
+        pub last_valid_block_height: u64,
+        fetched_at: Instant,
+    }
+
+    pub struct BlockhashProvider {
+        refresh_interval: Duration,
+        cached: RwLock<Option<CachedBlockhash>>,
+    }
+
+    impl BlockhashProvider {
+        pub fn new(refresh_interval: Duration) -> Self {
+            Self { refresh_interval, cached: RwLock::new(None) }
+        }
+
+        /// Runs forever, refreshing the cached blockhash on `refresh_interval`.
+        /// Meant to be driven by a single `tokio::spawn` from `MarketMaker::start`.
+        pub async fn run_refresh_loop(&self, app_state: std::sync::Arc<AppState>) {
+            loop {
+                match Self::fetch_with_retry(&app_state, 5).await {
+                    Ok(fresh) => {
+                        *self.cached.write().await = Some(fresh);
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "blockhash refresh failed, serving stale cache");
+                    }
+                }
+                sleep(self.refresh_interval).await;
+            }
+        }
+
+        /// Bounded retry with exponential backoff around a single
+        /// blockhash + last-valid-block-height fetch, so one transient RPC
+        /// error doesn't poison the cache or abort a trade.
+        async fn fetch_with_retry(app_state: &AppState, max_attempts: u32) -> Result<CachedBlockhash, String> {
+            let mut backoff = Duration::from_millis(200);
+            let mut last_err = String::new();
+            for attempt in 1..=max_attempts {
+                match app_state.rpc_client.get_latest_blockhash_with_commitment(
+                    anchor_client::solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+                ) {
+                    Ok((blockhash, last_valid_block_height)) => {
+                        return Ok(CachedBlockhash { blockhash, last_valid_block_height, fetched_at: Instant::now() });
+                    }
+                    Err(e) => {
+                        last_err = e.to_string();
+                        if attempt < max_attempts {
+                            sleep(backoff).await;
+                            backoff = (backoff * 2).min(Duration::from_secs(2));
+                        }
+                    }
+                }
+            }
+            Err(format!("Failed to fetch blockhash after {} attempts: {}", max_attempts, last_err))
+        }
+
+        /// Cached blockhash, falling back to a direct (still retried) fetch
+        /// if the background loop hasn't populated the cache yet or it's
+        /// gone stale past twice the refresh interval.
+        pub async fn latest(&self, app_state: &AppState) -> Result<CachedBlockhash, String> {
+            if let Some(entry) = *self.cached.read().await {
+                if entry.fetched_at.elapsed() < self.refresh_interval * 2 {
+                    return Ok(entry);
+                }
+            }
+            Self::fetch_with_retry(app_state, 5).await
+        }
+
+        /// Lets TPU/skip-simulation senders stop retrying a transaction
+        /// once its blockhash has aged out, instead of resending against a
+        /// dead blockhash indefinitely.
+        pub fn is_blockhash_expired(last_valid_block_height: u64, current_block_height: u64) -> bool {
+            current_block_height > last_valid_block_height
+        }
+    }
+}
+
+/// WSOL wrap/unwrap behind an `RpcBackend` trait so the temp-account
+/// rent-exemption dance in `unwrap_wsol` can be driven by an in-process
+/// `solana-program-test` bank in tests, instead of needing a live
+/// validator every time this math changes.
+pub mod wsol_ops {
+    use std::sync::Arc;
+    use anchor_client::solana_sdk::{
+        account::Account,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+    use anchor_client::solana_client::rpc_client::RpcClient;
+    use solana_program_pack::Pack;
+    use spl_token::instruction::sync_native;
+    use spl_associated_token_account::get_associated_token_address;
+
+    /// The handful of RPC operations the wrap/unwrap flow needs. Narrow on
+    /// purpose: this isn't meant to grow into a general RPC facade, just
+    /// enough surface to swap a live validator for a `BanksClient` in tests.
+    #[async_trait::async_trait]
+    pub trait RpcBackend: Send + Sync {
+        async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, String>;
+        async fn get_account(&self, pubkey: &Pubkey) -> Result<Account, String>;
+        async fn get_latest_blockhash(&self) -> Result<Hash, String>;
+        async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, String>;
+        async fn send_and_confirm(
+            &self,
+            instructions: &[Instruction],
+            payer: &Pubkey,
+            signers: &[&Keypair],
+        ) -> Result<Signature, String>;
+    }
+
+    /// Production backend: wraps the same synchronous `RpcClient` every
+    /// other signing path in this file already goes through.
+    pub struct RpcClientBackend {
+        rpc_client: Arc<RpcClient>,
+    }
+
+    impl RpcClientBackend {
+        pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+            Self { rpc_client }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RpcBackend for RpcClientBackend {
+        async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, String> {
+            self.rpc_client.get_balance(pubkey)
+                .map_err(|e| format!("get_balance failed: {}", e))
+        }
+
+        async fn get_account(&self, pubkey: &Pubkey) -> Result<Account, String> {
+            self.rpc_client.get_account(pubkey)
+                .map_err(|e| format!("get_account failed: {}", e))
+        }
+
+        async fn get_latest_blockhash(&self) -> Result<Hash, String> {
+            self.rpc_client.get_latest_blockhash()
+                .map_err(|e| format!("get_latest_blockhash failed: {}", e))
+        }
+
+        async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, String> {
+            self.rpc_client.get_minimum_balance_for_rent_exemption(data_len)
+                .map_err(|e| format!("get_minimum_balance_for_rent_exemption failed: {}", e))
+        }
+
+        async fn send_and_confirm(
+            &self,
+            instructions: &[Instruction],
+            payer: &Pubkey,
+            signers: &[&Keypair],
+        ) -> Result<Signature, String> {
+            let recent_blockhash = self.get_latest_blockhash().await?;
+            let transaction = Transaction::new_signed_with_payer(instructions, Some(payer), signers, recent_blockhash);
+            self.rpc_client.send_and_confirm_transaction(&transaction)
+                .map_err(|e| format!("send_and_confirm_transaction failed: {}", e))
+        }
+    }
+
+    /// Wrap `amount` SOL into the wallet's WSOL ATA. Assumes the ATA
+    /// already exists, mirroring `MarketMaker::wrap_sol_to_wsol`.
+    pub async fn wrap_sol(backend: &dyn RpcBackend, wallet: &Keypair, amount: f64) -> Result<Signature, String> {
+        let wallet_pubkey = wallet.pubkey();
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        let lamports = (amount * 1_000_000_000.0) as u64;
+
+        let instructions = vec![
+            system_instruction::transfer(&wallet_pubkey, &wsol_account, lamports),
+            sync_native(&spl_token::id(), &wsol_account)
+                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?,
+        ];
+
+        backend.send_and_confirm(&instructions, &wallet_pubkey, &[wallet]).await
+    }
+
+    /// Unwrap `amount` SOL worth of WSOL by routing it through a freshly
+    /// created, rent-exempt temporary token account and closing it, which
+    /// releases the unwrap amount plus the temp account's rent back to the
+    /// wallet in one transaction. Returns `(lamports_returned, signature)`
+    /// so callers can assert the rent math lines up.
+    pub async fn unwrap_wsol(backend: &dyn RpcBackend, wallet: &Keypair, amount: f64) -> Result<(u64, Signature), String> {
+        let wallet_pubkey = wallet.pubkey();
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        let lamports_to_unwrap = (amount * 1_000_000_000.0) as u64;
+
+        let rent_exempt_lamports = backend
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .await?;
+
+        let temp_account = Keypair::new();
+
+        let instructions = vec![
+            system_instruction::create_account(
+                &wallet_pubkey,
+                &temp_account.pubkey(),
+                rent_exempt_lamports,
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &temp_account.pubkey(),
+                &spl_token::native_mint::id(),
+                &wallet_pubkey,
+            ).map_err(|e| format!("Failed to create initialize account instruction: {}", e))?,
+            spl_token::instruction::transfer(
+                &spl_token::id(),
+                &wsol_account,
+                &temp_account.pubkey(),
+                &wallet_pubkey,
+                &[&wallet_pubkey],
+                lamports_to_unwrap,
+            ).map_err(|e| format!("Failed to create transfer instruction: {}", e))?,
+            sync_native(&spl_token::id(), &temp_account.pubkey())
+                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?,
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                &temp_account.pubkey(),
+                &wallet_pubkey,
+                &wallet_pubkey,
+                &[&wallet_pubkey],
+            ).map_err(|e| format!("Failed to create close account instruction: {}", e))?,
+        ];
+
+        let signature = backend
+            .send_and_confirm(&instructions, &wallet_pubkey, &[wallet, &temp_account])
+            .await?;
+
+        Ok((lamports_to_unwrap + rent_exempt_lamports, signature))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use solana_program_test::ProgramTest;
+
+        /// Adapts a `BanksClient` bound to a running `ProgramTestContext`
+        /// to `RpcBackend`, so `wrap_sol`/`unwrap_wsol` can run against an
+        /// in-process bank instead of a live validator.
+        struct BanksClientBackend {
+            banks_client: tokio::sync::Mutex<solana_program_test::BanksClient>,
+        }
+
+        #[async_trait::async_trait]
+        impl RpcBackend for BanksClientBackend {
+            async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, String> {
+                self.banks_client.lock().await.get_balance(*pubkey).await
+                    .map_err(|e| format!("get_balance failed: {}", e))
+            }
+
+            async fn get_account(&self, pubkey: &Pubkey) -> Result<Account, String> {
+                self.banks_client.lock().await.get_account(*pubkey).await
+                    .map_err(|e| format!("get_account failed: {}", e))?
+                    .ok_or_else(|| format!("account {} not found", pubkey))
+            }
+
+            async fn get_latest_blockhash(&self) -> Result<Hash, String> {
+                self.banks_client.lock().await.get_latest_blockhash().await
+                    .map_err(|e| format!("get_latest_blockhash failed: {}", e))
+            }
+
+            async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, String> {
+                let rent = self.banks_client.lock().await.get_rent().await
+                    .map_err(|e| format!("get_rent failed: {}", e))?;
+                Ok(rent.minimum_balance(data_len))
+            }
+
+            async fn send_and_confirm(
+                &self,
+                instructions: &[Instruction],
+                payer: &Pubkey,
+                signers: &[&Keypair],
+            ) -> Result<Signature, String> {
+                let recent_blockhash = self.get_latest_blockhash().await?;
+                let transaction = Transaction::new_signed_with_payer(instructions, Some(payer), signers, recent_blockhash);
+                let signature = transaction.signatures[0];
+                self.banks_client.lock().await
+                    .process_transaction(transaction).await
+                    .map_err(|e| format!("process_transaction failed: {}", e))?;
+                Ok(signature)
+            }
+        }
+
+        /// Spins up a fresh in-process bank, funds a brand new wallet, and
+        /// creates its WSOL ATA so the wrap/unwrap flow has somewhere to land.
+        async fn setup() -> (BanksClientBackend, Keypair) {
+            let program_test = ProgramTest::default();
+            let ctx = program_test.start_with_context().await;
+            let wallet = Keypair::new();
+
+            let fund_ix = system_instruction::transfer(&ctx.payer.pubkey(), &wallet.pubkey(), 2_000_000_000);
+            let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+            let tx = Transaction::new_signed_with_payer(&[fund_ix], Some(&ctx.payer.pubkey()), &[&ctx.payer], blockhash);
+            ctx.banks_client.clone().process_transaction(tx).await.unwrap();
+
+            let (_wsol_account, create_ixs) = crate::core::token::create_wsol_account(wallet.pubkey())
+                .expect("create_wsol_account instructions");
+            let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+            let tx = Transaction::new_signed_with_payer(&create_ixs, Some(&wallet.pubkey()), &[&wallet], blockhash);
+            ctx.banks_client.clone().process_transaction(tx).await.unwrap();
+
+            let backend = BanksClientBackend {
+                banks_client: tokio::sync::Mutex::new(ctx.banks_client),
+            };
+            (backend, wallet)
+        }
+
+        #[tokio::test]
+        async fn wrap_then_unwrap_round_trip() {
+            let (backend, wallet) = setup().await;
+
+            wrap_sol(&backend, &wallet, 0.1).await.expect("wrap should succeed");
+
+            let wsol_account = get_associated_token_address(&wallet.pubkey(), &spl_token::native_mint::id());
+            let account = backend.get_account(&wsol_account).await.expect("WSOL ATA should exist");
+            let token_account = spl_token::state::Account::unpack(&account.data).expect("valid token account");
+            assert_eq!(token_account.amount, 100_000_000); // 0.1 SOL in lamports
+
+            let balance_before = backend.get_balance(&wallet.pubkey()).await.unwrap();
+            let (lamports_returned, _sig) = unwrap_wsol(&backend, &wallet, 0.04).await.expect("unwrap should succeed");
+
+            let rent_exempt_lamports = backend
+                .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+                .await.unwrap();
+            assert_eq!(lamports_returned, 40_000_000 + rent_exempt_lamports);
+
+            let balance_after = backend.get_balance(&wallet.pubkey()).await.unwrap();
+            assert!(balance_after > balance_before);
+        }
+    }
+}
+
+/// Vesting-style pacing for cumulative buy volume. An operator supplies a
+/// sorted list of `(unix_timestamp, target_cumulative_volume_sol)`
+/// checkpoints; `target_cumulative_volume` interpolates the target for `now`
+/// and `remaining_ceiling_sol` compares it against what's actually landed so
+/// `execute_advanced_buy_debug`
+/// can cap the next buy instead of dumping the whole WSOL balance at once.
+pub mod volume_schedule {
+    use std::fs;
+    use std::path::Path;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    pub struct VolumeCheckpoint {
+        pub unix_timestamp: i64,
+        pub target_cumulative_volume_sol: f64,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct VolumeScheduler {
+        checkpoints: Vec<VolumeCheckpoint>,
+    }
+
+    impl VolumeScheduler {
+        pub fn new(mut checkpoints: Vec<VolumeCheckpoint>) -> Self {
+            checkpoints.sort_by_key(|c| c.unix_timestamp);
+            Self { checkpoints }
+        }
+
+        /// Load and sort a checkpoint list from a JSON file containing an
+        /// array of `{"unix_timestamp": ..., "target_cumulative_volume_sol": ...}`.
+        pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+            let raw = fs::read_to_string(path.as_ref())
+                .map_err(|e| format!("Failed to read volume schedule {}: {}", path.as_ref().display(), e))?;
+            let checkpoints: Vec<VolumeCheckpoint> = serde_json::from_str(&raw)
+                .map_err(|e| format!("Failed to parse volume schedule {}: {}", path.as_ref().display(), e))?;
+            Ok(Self::new(checkpoints))
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.checkpoints.is_empty()
+        }
+
+        /// Linearly interpolates the target cumulative volume for
+        /// `now_unix` between the two surrounding checkpoints. Clamps to
+        /// the first checkpoint before the schedule starts and to the last
+        /// one after it ends, rather than extrapolating past either edge.
+        pub fn target_cumulative_volume(&self, now_unix: i64) -> Option<f64> {
+            if self.checkpoints.is_empty() {
+                return None;
+            }
+
+            if now_unix <= self.checkpoints[0].unix_timestamp {
+                return Some(self.checkpoints[0].target_cumulative_volume_sol);
+            }
+
+            let last = self.checkpoints.last().unwrap();
+            if now_unix >= last.unix_timestamp {
+                return Some(last.target_cumulative_volume_sol);
+            }
+
+            let idx = self.checkpoints.partition_point(|c| c.unix_timestamp <= now_unix);
+            let before = &self.checkpoints[idx - 1];
+            let after = &self.checkpoints[idx];
+
+            let span = (after.unix_timestamp - before.unix_timestamp) as f64;
+            let elapsed = (now_unix - before.unix_timestamp) as f64;
+            let frac = if span > 0.0 { elapsed / span } else { 0.0 };
+
+            Some(before.target_cumulative_volume_sol
+                + frac * (after.target_cumulative_volume_sol - before.target_cumulative_volume_sol))
+        }
+
+        /// How much more buy volume can be spent right now without
+        /// overshooting the schedule. `None` means there's no schedule
+        /// configured, so the caller should fall back to its own sizing.
+        /// `Some(0.0)` means the bot is already at or ahead of target and
+        /// should skip the buy entirely.
+        pub fn remaining_ceiling_sol(&self, now_unix: i64, realized_cumulative_volume_sol: f64) -> Option<f64> {
+            let target = self.target_cumulative_volume(now_unix)?;
+            Some((target - realized_cumulative_volume_sol).max(0.0))
+        }
+    }
+}
+
+/// Read-only pool pricing for trades this bot didn't place itself. The
+/// gRPC feed parses third-party transactions against our target mint, but
+/// those don't carry a `build_raydium_swap`-computed price the way our
+/// own buys/sells do, so this mirrors just enough of `raydium_clmm`'s pool
+/// discovery to turn vault reserves / sqrt-price into a number, cached
+/// briefly so a burst of trades doesn't become a burst of RPC calls.
+pub mod pool_price {
+    use std::str::FromStr;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+    use tokio::time::Instant;
+    use anchor_client::solana_client::rpc_client::RpcClient;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use solana_program_pack::Pack;
+    use super::raydium_clmm::{self, PoolKind};
+
+    pub struct PoolPriceReader {
+        target_mint: Pubkey,
+        pool_kind: PoolKind,
+        ttl: Duration,
+        cached: Mutex<Option<(f64, Instant)>>,
+    }
+
+    impl PoolPriceReader {
+        pub fn new(target_mint: Pubkey, pool_kind: PoolKind, ttl: Duration) -> Self {
+            Self { target_mint, pool_kind, ttl, cached: Mutex::new(None) }
+        }
+
+        /// Current `target_mint` price in SOL, served from cache when
+        /// younger than `ttl` and refetched from the pool otherwise. Errors
+        /// (pool not found, account layout mismatch) are the caller's to
+        /// decide how to handle - this never panics on bad on-chain data.
+        pub async fn price(&self, rpc_client: &RpcClient) -> Result<f64, String> {
+            {
+                let cached = self.cached.lock().await;
+                if let Some((price, fetched_at)) = *cached {
+                    if fetched_at.elapsed() < self.ttl {
+                        return Ok(price);
+                    }
+                }
+            }
+
+            let price = match self.pool_kind {
+                PoolKind::Cpmm => Self::read_cpmm_price(rpc_client, &self.target_mint)?,
+                PoolKind::Clmm => Self::read_clmm_price(rpc_client, &self.target_mint)?,
+            };
+
+            *self.cached.lock().await = Some((price, Instant::now()));
+            Ok(price)
+        }
+
+        /// Decodes reserves straight out of the base/quote vault token
+        /// accounts, following the same `["pool", wsol_mint, target_mint]`
+        /// PDA `detect_pool_kind` already uses to find this pool.
+        fn read_cpmm_price(rpc_client: &RpcClient, target_mint: &Pubkey) -> Result<f64, String> {
+            let wsol_mint = spl_token::native_mint::id();
+            let program_id = Pubkey::from_str(raydium_clmm::CPMM_PROGRAM_ID).expect("static CPMM program id");
+            let (pool_state, _bump) = Pubkey::find_program_address(
+                &[b"pool", wsol_mint.as_ref(), target_mint.as_ref()],
+                &program_id,
+            );
+            let pool_account = rpc_client.get_account(&pool_state)
+                .map_err(|e| format!("Failed to fetch CPMM pool state {}: {}", pool_state, e))?;
+
+            // Layout (public Raydium CPMM IDL): discriminator(8) +
+            // amm_config(32) + pool_creator(32) + token_0_vault(32) +
+            // token_1_vault(32) + lp_mint(32) + token_0_mint(32) + ...
+            let data = &pool_account.data;
+            if data.len() < 8 + 32 * 6 {
+                return Err("CPMM pool account data too short to decode".to_string());
+            }
+            let mut offset = 8 + 32 + 32; // discriminator, amm_config, pool_creator
+            let token_0_vault = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad vault0".to_string())?;
+            offset += 32;
+            let token_1_vault = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad vault1".to_string())?;
+            offset += 32 + 32; // skip lp_mint
+            let token_0_mint = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad mint0".to_string())?;
+
+            let (wsol_vault, target_vault) = if token_0_mint == wsol_mint {
+                (token_0_vault, token_1_vault)
+            } else {
+                (token_1_vault, token_0_vault)
+            };
+
+            let wsol_reserve = Self::read_token_balance(rpc_client, &wsol_vault)?;
+            let target_reserve = Self::read_token_balance(rpc_client, &target_vault)?;
+            if target_reserve == 0 {
+                return Ok(0.0);
+            }
+
+            let target_decimals = Self::read_mint_decimals(rpc_client, target_mint)?;
+            let wsol_ui = wsol_reserve as f64 / 1_000_000_000.0;
+            let target_ui = target_reserve as f64 / 10f64.powi(target_decimals as i32);
+            Ok(wsol_ui / target_ui)
+        }
+
+        /// Decodes just enough of the CLMM pool account to recompute the
+        /// same sqrt-price math `RaydiumCLMM::build_swap_from_default_info`
+        /// uses, without needing a wallet or building swap instructions.
+        fn read_clmm_price(rpc_client: &RpcClient, target_mint: &Pubkey) -> Result<f64, String> {
+            let wsol_mint = spl_token::native_mint::id();
+            let program_id = Pubkey::from_str(raydium_clmm::CLMM_PROGRAM_ID).expect("static CLMM program id");
+            let (pool_state, _bump) = Pubkey::find_program_address(
+                &[b"pool", wsol_mint.as_ref(), target_mint.as_ref()],
+                &program_id,
+            );
+            let pool_account = rpc_client.get_account(&pool_state)
+                .map_err(|e| format!("Failed to fetch CLMM pool state {}: {}", pool_state, e))?;
+
+            let data = &pool_account.data;
+            if data.len() < 8 + 1 + 32 * 7 + 1 + 1 + 2 + 16 + 16 {
+                return Err("CLMM pool account data too short to decode".to_string());
+            }
+            let mut offset = 8 + 1 + 32; // discriminator, bump, amm_config
+            offset += 32; // owner
+            let token_mint_0 = Pubkey::try_from(&data[offset..offset + 32]).map_err(|_| "bad mint0".to_string())?;
+            offset += 32 + 32; // token_mint_1, then skip to vaults
+            offset += 32 + 32; // token_vault_0, token_vault_1
+            offset += 32; // observation_state
+            let decimals_0 = data[offset];
+            let decimals_1 = data[offset + 1];
+            offset += 2 + 2; // decimals, tick_spacing
+            offset += 16; // liquidity
+            let sqrt_price_x64 = u128::from_le_bytes(
+                data[offset..offset + 16].try_into().map_err(|_| "bad sqrt_price".to_string())?,
+            );
+
+            let raw = raydium_clmm::sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1);
+            // `raw` is token1 per token0; quote the target token in SOL.
+            Ok(if token_mint_0 != wsol_mint {
+                raw
+            } else if raw > 0.0 {
+                1.0 / raw
+            } else {
+                0.0
+            })
+        }
+
+        fn read_token_balance(rpc_client: &RpcClient, vault: &Pubkey) -> Result<u64, String> {
+            let account = rpc_client.get_account(vault)
+                .map_err(|e| format!("Failed to fetch vault {}: {}", vault, e))?;
+            let token_account = spl_token::state::Account::unpack(&account.data)
+                .map_err(|e| format!("Failed to decode vault {}: {}", vault, e))?;
+            Ok(token_account.amount)
+        }
+
+        fn read_mint_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8, String> {
+            let mint_account = rpc_client.get_account(mint)
+                .map_err(|e| format!("Failed to fetch mint {}: {}", mint, e))?;
+            let mint_state = spl_token::state::Mint::unpack(&mint_account.data)
+                .map_err(|e| format!("Failed to decode mint {}: {}", mint, e))?;
+            Ok(mint_state.decimals)
+        }
+    }
+}
+
+/// Which side of a price threshold a trigger order watches for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerDirection {
+    Above,
+    Below,
+}
+
+/// The forced action a trigger order takes once its threshold is crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerAction {
+    ForceBuy,
+    ForceSell,
+}
+
+/// A stop-loss / take-profit style rule evaluated against every price update
+/// from the `GlobalPriceMonitor`. Lets an operator defend a price floor or
+/// take profit at a ceiling independent of the statistical buy/sell ratio.
+#[derive(Debug, Clone)]
+pub struct TriggerOrder {
+    pub direction: TriggerDirection,
+    pub price_threshold: f64,
+    pub action: TriggerAction,
+    /// SOL amount for ForceBuy, or sell percentage (0-1) for ForceSell.
+    pub size: f64,
+    pub repeating: bool,
+}
+
+/// Holds the active trigger orders and decides, on each price update,
+/// whether any should preempt the normal volume-wave/guardian cadence.
+/// Rules survive wallet rotation since they live on the manager, not on any
+/// particular wallet.
+pub struct TriggerOrderManager {
+    /// Each order paired with the last time it fired, so a repeating order
+    /// whose threshold stays crossed (e.g. a stop-loss in a sustained
+    /// downtrend) can't fire a real trade on every single loop iteration.
+    orders: Mutex<Vec<(TriggerOrder, Option<Instant>)>>,
+}
+
+impl TriggerOrderManager {
+    /// Minimum gap between repeat fires of the same repeating trigger order.
+    const MIN_REFIRE_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new() -> Self {
+        Self { orders: Mutex::new(Vec::new()) }
+    }
+
+    pub async fn add_order(&self, order: TriggerOrder) {
+        self.orders.lock().await.push((order, None));
+    }
+
+    /// Return the orders that cross their threshold at `price`, removing
+    /// one-shot orders from the active set (repeating ones stay armed, but
+    /// won't fire again until `MIN_REFIRE_INTERVAL` has passed).
+    pub async fn evaluate(&self, price: f64) -> Vec<TriggerOrder> {
+        let mut orders = self.orders.lock().await;
+        let mut fired = Vec::new();
+        let now = Instant::now();
+        orders.retain_mut(|(order, last_fired)| {
+            let crossed = match order.direction {
+                TriggerDirection::Above => price >= order.price_threshold,
+                TriggerDirection::Below => price <= order.price_threshold,
+            };
+            if !crossed {
+                return true;
+            }
+            let ready = last_fired.map_or(true, |t| now.duration_since(t) >= Self::MIN_REFIRE_INTERVAL);
+            if !ready {
+                return true;
+            }
+            fired.push(order.clone());
+            *last_fired = Some(now);
+            order.repeating
+        });
+        fired
+    }
+}
+
+/// Bounded set of recently observed transaction signatures, used to drop
+/// duplicates when the same confirmed transaction arrives from more than
+/// one gRPC endpoint. Capacity-bounded FIFO so it stays cheap to check
+/// without growing unbounded over a long session.
+struct SeenSignatures {
+    capacity: usize,
+    seen: std::collections::HashSet<Signature>,
+    order: VecDeque<Signature>,
+}
+
+impl SeenSignatures {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: std::collections::HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns `true` if `signature` was already seen (and is therefore a
+    /// duplicate to be dropped); otherwise records it and returns `false`.
+    fn check_and_insert(&mut self, signature: Signature) -> bool {
+        if !self.seen.insert(signature) {
+            return true;
+        }
+        self.order.push_back(signature);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        false
+    }
+}
+
+/// A trade's expected balance movement, published the moment it is sent so
+/// concurrent balance checks on the same wallet don't race the still-in-flight
+/// confirmation.
+#[derive(Debug, Clone)]
+pub struct PendingTrade {
+    pub signature: Signature,
+    pub wallet: Pubkey,
+    pub sol_delta_lamports: i64,
+    pub wsol_delta_lamports: i64,
+    pub token_delta: i64,
+    pub submitted_at: Instant,
+}
+
+/// Tracks in-flight trades per wallet so balance-management code can compute
+/// an *effective* balance (confirmed ± pending) instead of racing in-flight
+/// transactions.
+pub struct PendingLedger {
+    entries: Mutex<HashMap<Signature, PendingTrade>>,
+    entry_ttl: Duration,
+}
+
+impl PendingLedger {
+    pub fn new(entry_ttl: Duration) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), entry_ttl }
+    }
+
+    /// Publish a newly-sent trade's expected deltas into the ledger. Cleared
+    /// either by `confirm` once the GRPC feed observes the signature
+    /// on-chain, or by `sweep_expired` if it never does.
+    pub async fn publish(&self, entry: PendingTrade) {
+        self.entries.lock().await.insert(entry.signature, entry);
+    }
+
+    /// Drop an entry once its signature has been confirmed on-chain.
+    pub async fn confirm(&self, signature: &Signature) {
+        self.entries.lock().await.remove(signature);
+    }
+
+    /// Drop entries older than `entry_ttl` that were never confirmed
+    /// (dropped transaction, expired blockhash, etc).
+    pub async fn sweep_expired(&self) {
+        let ttl = self.entry_ttl;
+        self.entries.lock().await.retain(|_, e| e.submitted_at.elapsed() < ttl);
+    }
+
+    /// confirmed_balance ± the sum of pending deltas for `wallet`.
+    pub async fn effective_sol_lamports(&self, wallet: &Pubkey, confirmed: u64) -> u64 {
+        let delta: i64 = self.entries.lock().await.values()
+            .filter(|e| &e.wallet == wallet)
+            .map(|e| e.sol_delta_lamports)
+            .sum();
+        (confirmed as i64 + delta).max(0) as u64
+    }
+
+    pub async fn effective_wsol_lamports(&self, wallet: &Pubkey, confirmed: u64) -> u64 {
+        let delta: i64 = self.entries.lock().await.values()
+            .filter(|e| &e.wallet == wallet)
+            .map(|e| e.wsol_delta_lamports)
+            .sum();
+        (confirmed as i64 + delta).max(0) as u64
+    }
+}
+
+/// Tracks consecutive failures per (wallet, operation) so a wallet with a
+/// stuck ATA, insufficient SOL, or a blacklisted address can be quarantined
+/// instead of repeatedly burning fees.
+#[derive(Debug, Clone)]
+struct WalletErrorState {
+    consecutive_failures: u32,
+    last_failure: Instant,
+    quarantined_until: Option<Instant>,
+}
+
+pub struct ErrorTracking {
+    states: HashMap<(String, String), WalletErrorState>,
+    max_consecutive_failures: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl ErrorTracking {
+    pub fn new(max_consecutive_failures: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            states: HashMap::new(),
+            max_consecutive_failures,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    fn key(wallet: &Pubkey, operation: &str) -> (String, String) {
+        (wallet.to_string(), operation.to_string())
+    }
+
+    /// Record a failed operation, quarantining the wallet with an
+    /// exponentially growing cooldown once `max_consecutive_failures` is hit.
+    pub fn record_failure(&mut self, wallet: &Pubkey, operation: &str) {
+        let now = Instant::now();
+        let entry = self.states.entry(Self::key(wallet, operation)).or_insert(WalletErrorState {
+            consecutive_failures: 0,
+            last_failure: now,
+            quarantined_until: None,
+        });
+        entry.consecutive_failures += 1;
+        entry.last_failure = now;
+
+        if entry.consecutive_failures >= self.max_consecutive_failures {
+            let backoff_factor = 1u64 << entry.consecutive_failures.saturating_sub(self.max_consecutive_failures).min(16);
+            let cooldown = (self.base_delay * backoff_factor as u32).min(self.max_delay);
+            entry.quarantined_until = Some(now + cooldown);
+            warn!(%wallet, operation, consecutive_failures = entry.consecutive_failures, cooldown = ?cooldown, "wallet quarantined");
+        }
+    }
+
+    /// Reset the failure counter for a (wallet, operation) pair on success.
+    pub fn record_success(&mut self, wallet: &Pubkey, operation: &str) {
+        self.states.remove(&Self::key(wallet, operation));
+    }
+
+    /// Whether the wallet is currently serving out a quarantine cooldown for
+    /// this operation kind.
+    pub fn is_quarantined(&self, wallet: &Pubkey, operation: &str) -> bool {
+        self.states
+            .get(&Self::key(wallet, operation))
+            .and_then(|s| s.quarantined_until)
+            .map(|until| Instant::now() < until)
+            .unwrap_or(false)
+    }
+}
+
+/// A priority-ordered source feeding the current token price. Lower
+/// priority value wins when multiple sources are fresh, reflecting how
+/// trustworthy each is: live pool reserves beat a parsed trade, which
+/// beats a best-effort aggregator quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PriceSourceKind {
+    /// Derived from live Raydium pool state (CPMM reserves or CLMM sqrt price).
+    PoolReserves,
+    /// Price attached to the last GRPC-observed trade for this mint; that
+    /// price is read from pool state when the trade is observed, not parsed
+    /// from the trade's own amounts.
+    GrpcTrade,
+    /// Jupiter v6 aggregator quote, used only when nothing fresher exists.
+    AggregatorQuote,
+}
+
+impl PriceSourceKind {
+    /// Fallback order, most to least trustworthy.
+    const PRIORITY: [PriceSourceKind; 3] = [
+        PriceSourceKind::PoolReserves,
+        PriceSourceKind::GrpcTrade,
+        PriceSourceKind::AggregatorQuote,
+    ];
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PriceSample {
+    price: f64,
+    confidence: f64,
+    observed_at: Instant,
+}
+
+/// Multi-source price oracle with staleness-based fallback. Each source
+/// records its own latest sample independently; `resolve` walks the
+/// priority chain and returns the first sample still within
+/// `max_staleness`, so a single stale or missing source degrades
+/// gracefully instead of blocking or lying about freshness.
+pub struct PriceOracle {
+    max_staleness: Duration,
+    samples: Mutex<HashMap<PriceSourceKind, PriceSample>>,
+}
+
+impl PriceOracle {
+    pub fn new(max_staleness: Duration) -> Self {
+        Self {
+            max_staleness,
+            samples: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn record(&self, source: PriceSourceKind, price: f64, confidence: f64) {
+        if price <= 0.0 {
+            return;
+        }
+        let mut samples = self.samples.lock().await;
+        samples.insert(source, PriceSample { price, confidence, observed_at: Instant::now() });
+    }
+
+    /// Highest-priority sample that hasn't aged past `max_staleness`, or
+    /// `None` if every source is stale or has never reported.
+    async fn resolve(&self) -> Option<PriceSample> {
+        let samples = self.samples.lock().await;
+        PriceSourceKind::PRIORITY.iter().find_map(|kind| {
+            samples.get(kind).filter(|s| s.observed_at.elapsed() < self.max_staleness).copied()
+        })
+    }
+}
+
+/// Lamport-denominated balance, used anywhere SOL/WSOL amounts are
+/// carried through arithmetic (fee reserves, wrap/unwrap sizing, random
+/// buy-amount sizing). All math is checked `u64` arithmetic so a bad
+/// percentage or multiplier can't silently round into a negative or
+/// overflowed lamport count the way the equivalent f64 math can; SOL is
+/// only used at the edges for display/logging and RPC call construction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LamportAmount(u64);
+
+impl LamportAmount {
+    pub const ZERO: LamportAmount = LamportAmount(0);
+
+    pub fn from_lamports(lamports: u64) -> Self {
+        LamportAmount(lamports)
+    }
+
+    /// Converts a SOL amount for construction purposes only (e.g. reading a
+    /// config threshold expressed in SOL). Not meant to be used mid-pipeline
+    /// for balance math.
+    pub fn from_sol(sol: f64) -> Result<Self, String> {
+        if !sol.is_finite() || sol < 0.0 {
+            return Err(format!("Invalid SOL amount: {}", sol));
+        }
+        let lamports = sol * 1_000_000_000.0;
+        if lamports > u64::MAX as f64 {
+            return Err(format!("SOL amount {} overflows lamports", sol));
+        }
+        Ok(LamportAmount(lamports.round() as u64))
+    }
+
+    pub fn lamports(&self) -> u64 {
+        self.0
+    }
+
+    /// For display/logging only.
+    pub fn to_sol(&self) -> f64 {
+        self.0 as f64 / 1_000_000_000.0
+    }
+
+    pub fn checked_add(&self, other: LamportAmount) -> Result<Self, String> {
+        self.0.checked_add(other.0)
+            .map(LamportAmount)
+            .ok_or_else(|| format!("Lamport overflow adding {} + {}", self.0, other.0))
+    }
+
+    pub fn checked_sub(&self, other: LamportAmount) -> Result<Self, String> {
+        self.0.checked_sub(other.0)
+            .map(LamportAmount)
+            .ok_or_else(|| format!("Lamport underflow subtracting {} from {}", other.0, self.0))
+    }
+
+    /// Saturates to zero instead of erroring, for "reserve at most what we
+    /// have" cases where underflowing to zero is the correct behavior.
+    pub fn saturating_sub(&self, other: LamportAmount) -> Self {
+        LamportAmount(self.0.saturating_sub(other.0))
+    }
+
+    /// Multiplies by a fraction expressed as basis points (1/10000ths), e.g.
+    /// 7500 for 75%. Fixed-point so repeated wrap/unwrap sizing can never
+    /// drift the way float percentages of floats do.
+    pub fn checked_mul_bps(&self, bps: u32) -> Result<Self, String> {
+        (self.0 as u128)
+            .checked_mul(bps as u128)
+            .map(|v| v / 10_000)
+            .filter(|v| *v <= u64::MAX as u128)
+            .map(|v| LamportAmount(v as u64))
+            .ok_or_else(|| format!("Lamport overflow multiplying {} by {} bps", self.0, bps))
+    }
+
+    /// Multiplies by an arbitrary `0.0..=X` ratio (e.g. a random
+    /// in-range multiplier). The ratio is converted to basis points first so
+    /// the actual multiplication stays in checked integer arithmetic.
+    pub fn checked_mul_ratio(&self, ratio: f64) -> Result<Self, String> {
+        if !ratio.is_finite() || ratio < 0.0 {
+            return Err(format!("Invalid multiplier ratio: {}", ratio));
+        }
+        let bps = (ratio * 10_000.0).round();
+        if bps > u32::MAX as f64 {
+            return Err(format!("Multiplier ratio {} too large", ratio));
+        }
+        self.checked_mul_bps(bps as u32)
+    }
+}
+
+// Activity tracking structures for token analysis
+#[derive(Debug, Clone)]
+pub struct TokenActivity {
+    pub timestamp: Instant,
+    pub is_buy: bool,
+    pub volume_sol: f64,
+    pub user: String,
+    pub price: f64,
+    /// Transaction signature this activity was observed in, when known.
+    /// Only gRPC-observed trades carry one today; it's what `persistence`
+    /// keys trade rows on so a later `backfill` pass doesn't duplicate
+    /// what live monitoring already recorded.
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct TokenActivityReport {
+    pub total_trades: u32,
+    pub buy_trades: u32,
+    pub sell_trades: u32,
+    pub total_volume_sol: f64,
+    pub buy_volume_sol: f64,
+    pub sell_volume_sol: f64,
+    pub average_price: f64,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub unique_traders: u32,
+    pub report_period_minutes: u64,
+    /// Set when a hot trader's per-shard ring buffer evicted an entry that
+    /// was still within the report window, so the figures above undercount
+    /// rather than exhaustively covering every trade in the window.
+    pub truncated: bool,
+}
+
+/// Sharded replacement for a single `Mutex<VecDeque<TokenActivity>>`. Under
+/// heavy trade flow every `add_token_activity` call was serializing on one
+/// lock; here each trader gets their own shard (a small ring buffer plus a
+/// couple of atomic counters) in a `DashMap`, so ingestion for different
+/// traders no longer contends the same mutex. Reporting folds over shards
+/// instead of draining one big deque.
+pub mod activity_store {
+    use super::TokenActivity;
+    use dashmap::DashMap;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    /// Recent activities kept per shard before the oldest is evicted. This
+    /// cap is per-trader, not global like the old store's, so a single hot
+    /// wallet can still outrun it within the reporting window; `last_evicted_at`
+    /// exists precisely to catch that instead of quietly under-reporting.
+    const MAX_PER_SHARD: usize = 20;
+
+    #[derive(Default)]
+    struct Shard {
+        recent: Mutex<VecDeque<TokenActivity>>,
+        trade_count: AtomicU32,
+        buy_count: AtomicU32,
+        /// Timestamp of the most recently evicted entry, if any. Lets
+        /// `ActivityStore::truncated_within` tell whether this shard's ring
+        /// buffer has discarded an entry that would still have fallen
+        /// inside a given reporting window.
+        last_evicted_at: Mutex<Option<Instant>>,
+    }
+
+    /// Concurrent activity store keyed by trader pubkey. Holding many small
+    /// per-shard locks instead of one big one means a burst of activity from
+    /// one trader no longer blocks ingestion for every other trader.
+    pub struct ActivityStore {
+        shards: DashMap<String, Shard>,
+    }
+
+    impl ActivityStore {
+        pub fn new() -> Self {
+            Self { shards: DashMap::new() }
+        }
+
+        /// Records `activity` under its trader's shard, trimming that
+        /// shard's ring buffer to `MAX_PER_SHARD` entries.
+        pub fn record(&self, activity: TokenActivity) {
+            let shard = self.shards.entry(activity.user.clone()).or_default();
+            shard.trade_count.fetch_add(1, Ordering::Relaxed);
+            if activity.is_buy {
+                shard.buy_count.fetch_add(1, Ordering::Relaxed);
+            }
+            let mut recent = shard.recent.lock().unwrap();
+            recent.push_back(activity);
+            if recent.len() > MAX_PER_SHARD {
+                if let Some(evicted) = recent.pop_front() {
+                    *shard.last_evicted_at.lock().unwrap() = Some(evicted.timestamp);
+                }
+            }
+        }
+
+        /// Flattens every shard's ring buffer into the activities observed
+        /// within `within` of `now`, the fold reporting needs in place of
+        /// locking one shared deque. Order across traders isn't meaningful
+        /// here, only membership in the window.
+        pub fn recent_within(&self, now: Instant, within: Duration) -> Vec<TokenActivity> {
+            self.shards
+                .iter()
+                .flat_map(|shard| {
+                    shard
+                        .recent
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|a| now.duration_since(a.timestamp) <= within)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+
+        /// Most recent `limit` activities across all shards, newest first.
+        pub fn latest(&self, limit: usize) -> Vec<TokenActivity> {
+            let mut all: Vec<TokenActivity> = self
+                .shards
+                .iter()
+                .flat_map(|shard| shard.recent.lock().unwrap().iter().cloned().collect::<Vec<_>>())
+                .collect();
+            all.sort_by_key(|a| std::cmp::Reverse(a.timestamp));
+            all.truncate(limit);
+            all
+        }
+
+        /// True if any shard has evicted an entry that would still have
+        /// fallen within `within` of `now` - i.e. `recent_within` for that
+        /// window is missing data rather than reporting a genuinely quiet
+        /// trader.
+        pub fn truncated_within(&self, now: Instant, within: Duration) -> bool {
+            self.shards.iter().any(|shard| {
+                shard
+                    .last_evicted_at
+                    .lock()
+                    .unwrap()
+                    .map_or(false, |evicted_at| now.duration_since(evicted_at) <= within)
+            })
+        }
+
+        /// Lifetime trade/buy counts across all shards, for lightweight
+        /// diagnostics; `recent_within` remains the source of truth for the
+        /// windowed `TokenActivityReport`.
+        pub fn total_counts(&self) -> (u32, u32) {
+            self.shards.iter().fold((0, 0), |(trades, buys), shard| {
+                (
+                    trades + shard.trade_count.load(Ordering::Relaxed),
+                    buys + shard.buy_count.load(Ordering::Relaxed),
+                )
+            })
+        }
+    }
+
+    impl Default for ActivityStore {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// Time-bucketed OHLCV aggregation over the raw `TokenActivity` stream, so
+/// downstream reporting can chart real price action at a chosen
+/// resolution instead of only `TokenActivityReport`'s flat window.
+pub mod candles {
+    use std::collections::{BTreeMap, HashMap};
+    use tokio::sync::Mutex;
+
+    /// A bucketing resolution candles can be requested at.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Interval {
+        OneMinute,
+        FiveMinutes,
+        OneHour,
+    }
+
+    impl Interval {
+        fn bucket_secs(self) -> i64 {
+            match self {
+                Interval::OneMinute => 60,
+                Interval::FiveMinutes => 5 * 60,
+                Interval::OneHour => 60 * 60,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Candle {
+        pub interval_start: i64,
+        pub open: f64,
+        /// `None` until a priced (>0) sample lands in this bucket - a bucket
+        /// that only ever sees price<=0 activity (e.g. a pool-price read
+        /// failure) has no real high/low to report.
+        pub high: Option<f64>,
+        pub low: Option<f64>,
+        pub close: f64,
+        pub volume_sol: f64,
+        pub trade_count: u32,
+        pub buy_volume: f64,
+        pub sell_volume: f64,
+        /// Set when a GRPC reconnect's detected data gap overlapped this
+        /// bucket, so consumers know its volume/trade_count likely
+        /// undercounts what actually happened in that window.
+        pub gap: bool,
+    }
+
+    impl Candle {
+        fn new(interval_start: i64) -> Self {
+            Self {
+                interval_start,
+                open: 0.0,
+                high: None,
+                low: None,
+                close: 0.0,
+                volume_sol: 0.0,
+                trade_count: 0,
+                buy_volume: 0.0,
+                sell_volume: 0.0,
+                gap: false,
+            }
+        }
+    }
+
+    /// Rolling set of candles for a single `Interval`, capped to the last
+    /// `max_buckets` so memory stays bounded the same way `token_activities`
+    /// caps its raw entries. Keyed by `interval_start` rather than
+    /// insertion order, so a trade that arrives slightly out of order still
+    /// lands in the right bucket and iteration stays monotonic.
+    struct IntervalSeries {
+        bucket_secs: i64,
+        max_buckets: usize,
+        buckets: BTreeMap<i64, Candle>,
+    }
+
+    impl IntervalSeries {
+        fn new(bucket_secs: i64, max_buckets: usize) -> Self {
+            Self { bucket_secs, max_buckets, buckets: BTreeMap::new() }
+        }
+
+        fn record(&mut self, unix_timestamp: i64, price: f64, volume_sol: f64, is_buy: bool) {
+            let interval_start = unix_timestamp.div_euclid(self.bucket_secs) * self.bucket_secs;
+            let candle = self.buckets.entry(interval_start).or_insert_with(|| Candle::new(interval_start));
+
+            // A non-positive price can't open/close/high/low a candle, but
+            // the trade still happened and still counts toward volume.
+            if price > 0.0 {
+                match (candle.high, candle.low) {
+                    (None, _) | (_, None) => {
+                        // First priced sample this bucket has seen.
+                        candle.open = price;
+                        candle.high = Some(price);
+                        candle.low = Some(price);
+                        candle.close = price;
+                    }
+                    (Some(high), Some(low)) => {
+                        candle.high = Some(high.max(price));
+                        candle.low = Some(low.min(price));
+                        candle.close = price;
+                    }
+                }
+            }
+
+            candle.volume_sol += volume_sol;
+            candle.trade_count += 1;
+            if is_buy {
+                candle.buy_volume += volume_sol;
+            } else {
+                candle.sell_volume += volume_sol;
+            }
+
+            while self.buckets.len() > self.max_buckets {
+                if let Some(&oldest) = self.buckets.keys().next() {
+                    self.buckets.remove(&oldest);
+                } else {
+                    break;
+                }
+            }
+        }
+
+        /// Flags every bucket whose interval overlaps `[from_unix, to_unix]`
+        /// as a gap, creating empty buckets to hold the flag where no trade
+        /// landed during the outage.
+        fn mark_gap(&mut self, from_unix: i64, to_unix: i64) {
+            let first = from_unix.div_euclid(self.bucket_secs) * self.bucket_secs;
+            let last = to_unix.div_euclid(self.bucket_secs) * self.bucket_secs;
+            let mut bucket_start = first;
+            while bucket_start <= last {
+                self.buckets
+                    .entry(bucket_start)
+                    .or_insert_with(|| Candle::new(bucket_start))
+                    .gap = true;
+                bucket_start += self.bucket_secs;
+            }
+
+            while self.buckets.len() > self.max_buckets {
+                if let Some(&oldest) = self.buckets.keys().next() {
+                    self.buckets.remove(&oldest);
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// How many buckets to retain per interval - roughly 4 hours of 1m
+    /// candles, 20 hours of 5m candles, and 10 days of 1h candles.
+    const DEFAULT_MAX_BUCKETS: usize = 240;
+
+    /// Rolls the incoming activity stream into OHLCV candles at 1m/5m/1h
+    /// resolutions simultaneously.
+    pub struct CandleAggregator {
+        series: Mutex<HashMap<Interval, IntervalSeries>>,
+    }
+
+    impl CandleAggregator {
+        pub fn new() -> Self {
+            let mut series = HashMap::new();
+            for interval in [Interval::OneMinute, Interval::FiveMinutes, Interval::OneHour] {
+                series.insert(interval, IntervalSeries::new(interval.bucket_secs(), DEFAULT_MAX_BUCKETS));
+            }
+            Self { series: Mutex::new(series) }
+        }
+
+        /// Rolls one activity sample into every tracked interval's candles.
+        pub async fn record(&self, unix_timestamp: i64, price: f64, volume_sol: f64, is_buy: bool) {
+            let mut series = self.series.lock().await;
+            for interval_series in series.values_mut() {
+                interval_series.record(unix_timestamp, price, volume_sol, is_buy);
+            }
+        }
+
+        /// Up to `limit` candles for `interval`, newest first.
+        pub async fn get_candles(&self, interval: Interval, limit: usize) -> Vec<Candle> {
+            let series = self.series.lock().await;
+            match series.get(&interval) {
+                Some(s) => s.buckets.values().rev().take(limit).copied().collect(),
+                None => Vec::new(),
+            }
+        }
+
+        /// Flags every interval's buckets spanning `[from_unix, to_unix]` as
+        /// a data gap, e.g. after a GRPC reconnect whose outage window
+        /// overlapped them.
+        pub async fn mark_gap(&self, from_unix: i64, to_unix: i64) {
+            let mut series = self.series.lock().await;
+            for interval_series in series.values_mut() {
+                interval_series.mark_gap(from_unix, to_unix);
+            }
+        }
+    }
+
+    impl Default for CandleAggregator {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// Configuration for market maker bot with advanced multi-wallet support
+#[derive(Clone)]
+pub struct MarketMakerConfig {
+    pub yellowstone_grpc_http: String,
+    pub yellowstone_grpc_token: String,
+    pub app_state: Arc<AppState>,
+    pub target_token_mint: String,
+    pub slippage: u64,
+    pub randomization_config: RandomizationConfig,
+    pub enable_multi_wallet: bool,
+    pub max_concurrent_trades: usize,
+    pub enable_telegram_notifications: bool,
+    /// Address the Prometheus `/metrics` endpoint is served on.
+    pub metrics_addr: String,
+    /// Address the `/ticker`, `/trades`, and `/candles` JSON API is served
+    /// on, so dashboards/alerting can poll live state instead of scraping
+    /// stdout.
+    pub data_api_addr: String,
+    /// Hard timeout for Jupiter v6 quote/swap-instruction requests; on expiry
+    /// the buy path silently falls back to the direct Raydium route.
+    pub jupiter_quote_timeout_ms: u64,
+    /// Maximum age, in milliseconds, a price sample may reach before the
+    /// `PriceOracle` stops treating it as fresh.
+    pub price_staleness_limit_ms: u64,
+    /// Maximum time, in milliseconds, a GRPC endpoint may go without
+    /// delivering a message before its stream is considered stale and torn
+    /// down for a fresh reconnect - analogous to `price_staleness_limit_ms`
+    /// but for the subscription itself rather than a price sample.
+    pub grpc_stream_staleness_ms: u64,
+    /// When set, swap transactions are fanned out directly to upcoming
+    /// leaders' TPU ports instead of going through `rpc_client`/
+    /// `rpc_nonblocking_client`.
+    pub use_tpu: bool,
+    /// Percentile of recent per-slot prioritization fees to bid (e.g. 0.75 = p75).
+    pub priority_fee_percentile: f64,
+    pub priority_fee_min_micro_lamports: u64,
+    pub priority_fee_max_micro_lamports: u64,
+    /// EWMA smoothing factor applied across consecutive trades' fee samples.
+    pub priority_fee_ewma_alpha: f64,
+    /// Extra Yellowstone gRPC endpoints, each as `(http_url, x_token)`,
+    /// monitored concurrently alongside `yellowstone_grpc_http` so one
+    /// endpoint dropping doesn't leave the bot blind mid-session.
+    pub additional_grpc_endpoints: Vec<(String, String)>,
+    /// How often the background task refreshes the cached blockhash used
+    /// by every swap/wrap/unwrap signer.
+    pub blockhash_refresh_interval_ms: u64,
+    /// Skip the leader's preflight simulation on submit. Stealth buys set
+    /// this so a slow simulation doesn't eat into the race against other
+    /// bots; account-setup transactions leave it off to fail fast.
+    pub submit_skip_preflight: bool,
+    /// How many times the leader should rebroadcast the transaction while
+    /// we wait for a signature status to appear.
+    pub submit_max_retries: usize,
+    /// How long `submit_transaction` polls `get_signature_statuses` before
+    /// giving up and returning `SubmitError::Timeout`.
+    pub submit_confirmation_timeout_ms: u64,
+    /// Commitment level `submit_transaction` waits for before treating a
+    /// send as confirmed.
+    pub submit_commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig,
+    /// Optional path to a JSON volume-checkpoint schedule (see
+    /// `volume_schedule::VolumeScheduler`). When set, stealth buy sizing is
+    /// capped so realized cumulative volume tracks the schedule instead of
+    /// racing ahead on current WSOL balance alone.
+    pub volume_schedule_path: Option<String>,
+    /// Postgres connection string for `persistence::TradeStore`. When
+    /// unset, persistence is entirely disabled and the bot behaves exactly
+    /// as it did before trade/report history was durable.
+    pub database_url: Option<String>,
+    /// Maximum number of pooled Postgres connections.
+    pub db_max_connections: usize,
+    /// Path to a CA certificate used to verify the Postgres server over
+    /// TLS. Ignored unless `database_url` is also set.
+    pub db_ssl_ca_cert_path: Option<String>,
+    /// Path to a combined client certificate + private key PEM for mutual
+    /// TLS, for providers that require client auth. Ignored unless
+    /// `db_ssl_ca_cert_path` is also set.
+    pub db_ssl_client_key_path: Option<String>,
+    /// Widest `order_book` spread, in basis points of mid-price, the bot
+    /// will still generate stealth volume into. Wider than this and a
+    /// buy is skipped rather than trading into an abnormally thin book.
+    pub max_quote_spread_bps: f64,
+}
+
+impl MarketMakerConfig {
+    /// Create a new MarketMakerConfig with stealth mode settings
+    pub fn stealth_mode(
+        yellowstone_grpc_http: String,
+        yellowstone_grpc_token: String,
+        app_state: Arc<AppState>,
+        target_token_mint: String,
+    ) -> Self {
+        Self {
+            yellowstone_grpc_http,
+            yellowstone_grpc_token,
+            app_state,
+            target_token_mint,
+            slippage: 1000, // 10%
+            randomization_config: RandomizationConfig::stealth_mode(),
+            enable_multi_wallet: true,
+            max_concurrent_trades: 3,
+            enable_telegram_notifications: true,
+            metrics_addr: "0.0.0.0:9898".to_string(),
+            data_api_addr: "0.0.0.0:9899".to_string(),
+            jupiter_quote_timeout_ms: 3000,
+            price_staleness_limit_ms: 15_000,
+            grpc_stream_staleness_ms: 90_000,
+            use_tpu: false,
+            priority_fee_percentile: 0.75,
+            priority_fee_min_micro_lamports: 1_000,
+            priority_fee_max_micro_lamports: 2_000_000,
+            priority_fee_ewma_alpha: 0.3,
+            additional_grpc_endpoints: Vec::new(),
+            blockhash_refresh_interval_ms: 2_000,
+            submit_skip_preflight: false,
+            submit_max_retries: 3,
+            submit_confirmation_timeout_ms: 15_000,
+            submit_commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+            volume_schedule_path: None,
+            database_url: None,
+            db_max_connections: 5,
+            db_ssl_ca_cert_path: None,
+            db_ssl_client_key_path: None,
+            max_quote_spread_bps: 300.0,
+        }
+    }
+
+    /// Create a new MarketMakerConfig with conservative settings
+    pub fn conservative_mode(
+        yellowstone_grpc_http: String,
+        yellowstone_grpc_token: String,
+        app_state: Arc<AppState>,
+        target_token_mint: String,
+    ) -> Self {
+        Self {
+            yellowstone_grpc_http,
+            yellowstone_grpc_token,
+            app_state,
+            target_token_mint,
+            slippage: 1500, // 15%
+            randomization_config: RandomizationConfig::conservative_mode(),
+            enable_multi_wallet: true,
+            max_concurrent_trades: 2,
+            enable_telegram_notifications: true,
+            metrics_addr: "0.0.0.0:9898".to_string(),
+            data_api_addr: "0.0.0.0:9899".to_string(),
+            jupiter_quote_timeout_ms: 3000,
+            price_staleness_limit_ms: 15_000,
+            grpc_stream_staleness_ms: 90_000,
+            use_tpu: false,
+            priority_fee_percentile: 0.75,
+            priority_fee_min_micro_lamports: 1_000,
+            priority_fee_max_micro_lamports: 2_000_000,
+            priority_fee_ewma_alpha: 0.3,
+            additional_grpc_endpoints: Vec::new(),
+            blockhash_refresh_interval_ms: 2_000,
+            submit_skip_preflight: false,
+            submit_max_retries: 3,
+            submit_confirmation_timeout_ms: 15_000,
+            submit_commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+            volume_schedule_path: None,
+            database_url: None,
+            db_max_connections: 5,
+            db_ssl_ca_cert_path: None,
+            db_ssl_client_key_path: None,
+            max_quote_spread_bps: 300.0,
+        }
+    }
+
+    /// Create a new MarketMakerConfig with default settings
+    pub fn new(
+        yellowstone_grpc_http: String,
+        yellowstone_grpc_token: String,
+        app_state: Arc<AppState>,
+        target_token_mint: String,
+    ) -> Self {
+        Self {
+            yellowstone_grpc_http,
+            yellowstone_grpc_token,
+            app_state,
+            target_token_mint,
+            slippage: 1000, // 10%
+            randomization_config: RandomizationConfig::default(),
+            enable_multi_wallet: true,
+            max_concurrent_trades: 2,
+            enable_telegram_notifications: true,
+            metrics_addr: "0.0.0.0:9898".to_string(),
+            data_api_addr: "0.0.0.0:9899".to_string(),
+            jupiter_quote_timeout_ms: 3000,
+            price_staleness_limit_ms: 15_000,
+            grpc_stream_staleness_ms: 90_000,
+            use_tpu: false,
+            priority_fee_percentile: 0.75,
+            priority_fee_min_micro_lamports: 1_000,
+            priority_fee_max_micro_lamports: 2_000_000,
+            priority_fee_ewma_alpha: 0.3,
+            additional_grpc_endpoints: Vec::new(),
+            blockhash_refresh_interval_ms: 2_000,
+            submit_skip_preflight: false,
+            submit_max_retries: 3,
+            submit_confirmation_timeout_ms: 15_000,
+            submit_commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+            volume_schedule_path: None,
+            database_url: None,
+            db_max_connections: 5,
+            db_ssl_ca_cert_path: None,
+            db_ssl_client_key_path: None,
+            max_quote_spread_bps: 300.0,
+        }
+    }
+}
+
+/// Live order-book depth, fed from the same gRPC transaction stream that
+/// already supplies trade activity. This venue (Raydium CPMM/CLMM) doesn't
+/// publish a native L2 feed the way a CLOB would, so levels are built from
+/// executed-trade flow as the best available proxy for where resting
+/// liquidity was last touched - a buy is assumed to have lifted an ask at
+/// its price, a sell to have hit a bid - giving quoting logic a spread and
+/// mid-price to react to instead of only trailing individual trades.
+pub mod order_book {
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
+    use tokio::sync::RwLock;
+
+    /// Wraps `f64` with a total order so prices can key a `BTreeMap`
+    /// (`f64` isn't `Ord` because of NaN). Order-book prices are never NaN
+    /// in practice, so `total_cmp` is exactly the ordering wanted.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Price(f64);
+
+    impl Eq for Price {}
+
+    impl PartialOrd for Price {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Price {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.total_cmp(&other.0)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Side {
+        Bid,
+        Ask,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Level {
+        pub price: f64,
+        pub size: f64,
+    }
+
+    #[derive(Debug, Default)]
+    struct Book {
+        bids: BTreeMap<Price, f64>,
+        asks: BTreeMap<Price, f64>,
+    }
+
+    /// Cap on resting levels per side. Trade prices are floats that rarely
+    /// repeat exactly, so without a cap every observed trade would grow the
+    /// book by a new entry instead of updating an existing one, leaking
+    /// memory over a long-running session.
+    const MAX_LEVELS_PER_SIDE: usize = 500;
+
+    pub struct OrderBook {
+        book: RwLock<Book>,
+    }
+
+    impl OrderBook {
+        pub fn new() -> Self {
+            Self { book: RwLock::new(Book::default()) }
+        }
+
+        /// Sets `price`'s resting size on `side`. A size of `0.0` (or
+        /// below) removes the level entirely rather than leaving a
+        /// zero-size entry behind to skew `depth`/best-price lookups.
+        /// Evicts the level furthest from the touched side's best price
+        /// once `MAX_LEVELS_PER_SIDE` is exceeded.
+        pub async fn update_level(&self, side: Side, price: f64, size: f64) {
+            let mut book = self.book.write().await;
+            let levels = match side {
+                Side::Bid => &mut book.bids,
+                Side::Ask => &mut book.asks,
+            };
+            if size <= 0.0 {
+                levels.remove(&Price(price));
+            } else {
+                levels.insert(Price(price), size);
+                while levels.len() > MAX_LEVELS_PER_SIDE {
+                    // Bids: best is the highest price, so the worst is the
+                    // lowest. Asks: best is the lowest price, so the worst
+                    // is the highest.
+                    let worst = match side {
+                        Side::Bid => levels.keys().next().copied(),
+                        Side::Ask => levels.keys().next_back().copied(),
+                    };
+                    match worst {
+                        Some(worst) => { levels.remove(&worst); }
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        /// Folds one executed trade into the book as a depth proxy: a buy
+        /// is taken to have consumed an ask at `price`, a sell a bid at
+        /// `price`, refreshing that side's touched level.
+        pub async fn record_trade(&self, is_buy: bool, price: f64, size: f64) {
+            if price <= 0.0 || size <= 0.0 {
+                return;
+            }
+            let side = if is_buy { Side::Ask } else { Side::Bid };
+            self.update_level(side, price, size).await;
+        }
+
+        /// Highest resting bid, if any.
+        pub async fn best_bid(&self) -> Option<f64> {
+            self.book.read().await.bids.keys().next_back().map(|p| p.0)
+        }
+
+        /// Lowest resting ask, if any.
+        pub async fn best_ask(&self) -> Option<f64> {
+            self.book.read().await.asks.keys().next().map(|p| p.0)
+        }
+
+        /// `best_ask - best_bid`, or `None` if either side is empty.
+        pub async fn spread(&self) -> Option<f64> {
+            let book = self.book.read().await;
+            let best_bid = book.bids.keys().next_back()?.0;
+            let best_ask = book.asks.keys().next()?.0;
+            Some(best_ask - best_bid)
+        }
+
+        /// Midpoint between the best bid and ask, or `None` if either side
+        /// is empty.
+        pub async fn mid_price(&self) -> Option<f64> {
+            let book = self.book.read().await;
+            let best_bid = book.bids.keys().next_back()?.0;
+            let best_ask = book.asks.keys().next()?.0;
+            Some((best_bid + best_ask) / 2.0)
+        }
+
+        /// Top `n` levels per side: bids best-first (descending price),
+        /// asks best-first (ascending price).
+        pub async fn depth(&self, n: usize) -> (Vec<Level>, Vec<Level>) {
+            let book = self.book.read().await;
+            let bids = book.bids.iter().rev().take(n).map(|(p, &size)| Level { price: p.0, size }).collect();
+            let asks = book.asks.iter().take(n).map(|(p, &size)| Level { price: p.0, size }).collect();
+            (bids, asks)
+        }
+    }
+
+    impl Default for OrderBook {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// Optional Postgres persistence for trade history and activity reports,
+/// entirely driven by `MarketMakerConfig`'s `database_url`/`db_*` fields.
+/// `record_trade` and `record_report` are no-ops when no `database_url` is
+/// configured, so the rest of the bot never has to branch on whether
+/// persistence is on; `backfill` returns an error in that case.
+pub mod persistence {
+    use std::str::FromStr;
+    use anchor_client::solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+    use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use anchor_client::solana_sdk::signature::Signature;
+    use deadpool_postgres::{Config as PoolConfig, Pool, Runtime};
+    use openssl::ssl::{SslConnector, SslFiletype, SslMethod, SslVerifyMode};
+    use postgres_openssl::MakeTlsConnector;
+    use solana_transaction_status::UiTransactionEncoding;
+    use tokio::sync::OnceCell;
+    use super::{MarketMakerConfig, TokenActivityReport};
+
+    /// One persisted trade row, whether observed live over gRPC or
+    /// replayed by `TradeStore::backfill`.
+    #[derive(Debug, Clone)]
+    pub struct TradeRecord {
+        pub signature: String,
+        pub is_buy: bool,
+        pub price: f64,
+        pub volume_sol: f64,
+        pub trader: String,
+        pub unix_timestamp: i64,
+    }
+
+    /// Optional Postgres-backed trade/report store. Connecting is kept
+    /// synchronous (pool construction alone doesn't need a connection) so
+    /// `MarketMaker::new` - which isn't `async` - can build one directly;
+    /// the actual connection and one-time schema migration happen lazily
+    /// on first use via `schema_ready`.
+    pub struct TradeStore {
+        pool: Option<Pool>,
+        schema_ready: OnceCell<()>,
+    }
+
+    impl TradeStore {
+        /// Builds the connection pool from `config.database_url`, or a
+        /// no-op store if it's unset.
+        pub fn connect(config: &MarketMakerConfig) -> Result<Self, String> {
+            let Some(database_url) = &config.database_url else {
+                return Ok(Self { pool: None, schema_ready: OnceCell::new() });
+            };
+
+            let mut pool_config = PoolConfig::new();
+            pool_config.url = Some(database_url.clone());
+            pool_config.pool = Some(deadpool_postgres::PoolConfig::new(config.db_max_connections));
+
+            let pool = match &config.db_ssl_ca_cert_path {
+                Some(ca_cert_path) => {
+                    let mut builder = SslConnector::builder(SslMethod::tls())
+                        .map_err(|e| format!("Failed to init TLS connector: {}", e))?;
+                    builder.set_ca_file(ca_cert_path)
+                        .map_err(|e| format!("Failed to load CA cert {}: {}", ca_cert_path, e))?;
+                    if let Some(client_key_path) = &config.db_ssl_client_key_path {
+                        builder.set_certificate_chain_file(client_key_path)
+                            .map_err(|e| format!("Failed to load client cert {}: {}", client_key_path, e))?;
+                        builder.set_private_key_file(client_key_path, SslFiletype::PEM)
+                            .map_err(|e| format!("Failed to load client key {}: {}", client_key_path, e))?;
+                    }
+                    builder.set_verify(SslVerifyMode::PEER);
+                    let connector = MakeTlsConnector::new(builder.build());
+                    pool_config.create_pool(Some(Runtime::Tokio1), connector)
+                        .map_err(|e| format!("Failed to create Postgres pool: {}", e))?
+                }
+                None => pool_config.create_pool(Some(Runtime::Tokio1), tokio_postgres::NoTls)
+                    .map_err(|e| format!("Failed to create Postgres pool: {}", e))?,
+            };
+
+            Ok(Self { pool: Some(pool), schema_ready: OnceCell::new() })
+        }
+
+        /// Runs the `CREATE TABLE IF NOT EXISTS` migration exactly once,
+        /// the first time this store is actually used.
+        async fn ensure_schema(&self, pool: &Pool) -> Result<(), String> {
+            self.schema_ready.get_or_try_init(|| async {
+                let client = pool.get().await.map_err(|e| format!("Failed to connect to Postgres: {}", e))?;
+                client.batch_execute(
+                    "CREATE TABLE IF NOT EXISTS trades (
+                        signature TEXT PRIMARY KEY,
+                        is_buy BOOLEAN NOT NULL,
+                        price DOUBLE PRECISION NOT NULL,
+                        volume_sol DOUBLE PRECISION NOT NULL,
+                        trader TEXT NOT NULL,
+                        unix_timestamp BIGINT NOT NULL
+                    );
+                    CREATE TABLE IF NOT EXISTS activity_reports (
+                        id BIGSERIAL PRIMARY KEY,
+                        generated_at_unix BIGINT NOT NULL,
+                        total_trades INTEGER NOT NULL,
+                        buy_trades INTEGER NOT NULL,
+                        sell_trades INTEGER NOT NULL,
+                        total_volume_sol DOUBLE PRECISION NOT NULL,
+                        buy_volume_sol DOUBLE PRECISION NOT NULL,
+                        sell_volume_sol DOUBLE PRECISION NOT NULL,
+                        average_price DOUBLE PRECISION NOT NULL,
+                        min_price DOUBLE PRECISION NOT NULL,
+                        max_price DOUBLE PRECISION NOT NULL,
+                        unique_traders INTEGER NOT NULL,
+                        report_period_minutes BIGINT NOT NULL,
+                        truncated BOOLEAN NOT NULL DEFAULT FALSE
+                    );"
+                ).await.map_err(|e| format!("Failed to run persistence migration: {}", e))?;
+                Ok::<(), String>(())
+            }).await?;
+            Ok(())
+        }
+
+        /// Upserts a trade row, keyed by signature so a live observation
+        /// and a later `backfill` pass over the same slot don't
+        /// double-count the same trade.
+        pub async fn record_trade(&self, trade: &TradeRecord) -> Result<(), String> {
+            let Some(pool) = &self.pool else { return Ok(()) };
+            self.ensure_schema(pool).await?;
+
+            let client = pool.get().await.map_err(|e| format!("Failed to get Postgres connection: {}", e))?;
+            client.execute(
+                "INSERT INTO trades (signature, is_buy, price, volume_sol, trader, unix_timestamp)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (signature) DO UPDATE SET
+                    is_buy = EXCLUDED.is_buy,
+                    price = EXCLUDED.price,
+                    volume_sol = EXCLUDED.volume_sol,
+                    trader = EXCLUDED.trader,
+                    unix_timestamp = EXCLUDED.unix_timestamp",
+                &[&trade.signature, &trade.is_buy, &trade.price, &trade.volume_sol, &trade.trader, &trade.unix_timestamp],
+            ).await.map_err(|e| format!("Failed to upsert trade {}: {}", trade.signature, e))?;
+            Ok(())
+        }
+
+        /// Inserts one row capturing a `TokenActivityReport` snapshot.
+        pub async fn record_report(&self, report: &TokenActivityReport, generated_at_unix: i64) -> Result<(), String> {
+            let Some(pool) = &self.pool else { return Ok(()) };
+            self.ensure_schema(pool).await?;
+
+            let client = pool.get().await.map_err(|e| format!("Failed to get Postgres connection: {}", e))?;
+            client.execute(
+                "INSERT INTO activity_reports (
+                    generated_at_unix, total_trades, buy_trades, sell_trades,
+                    total_volume_sol, buy_volume_sol, sell_volume_sol,
+                    average_price, min_price, max_price, unique_traders, report_period_minutes,
+                    truncated
+                 ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+                &[
+                    &generated_at_unix,
+                    &(report.total_trades as i32),
+                    &(report.buy_trades as i32),
+                    &(report.sell_trades as i32),
+                    &report.total_volume_sol,
+                    &report.buy_volume_sol,
+                    &report.sell_volume_sol,
+                    &report.average_price,
+                    &report.min_price,
+                    &report.max_price,
+                    &(report.unique_traders as i32),
+                    &(report.report_period_minutes as i64),
+                    &report.truncated,
+                ],
+            ).await.map_err(|e| format!("Failed to insert activity report: {}", e))?;
+            Ok(())
+        }
+
+        /// Replays confirmed on-chain trades for `target_mint` between
+        /// `start_unix` and `end_unix` into the same `trades` table a live
+        /// gRPC feed would populate, so analytics stay consistent whether
+        /// data arrived live or was backfilled. Returns the number of
+        /// trades persisted.
+        pub async fn backfill(
+            &self,
+            rpc_client: &RpcClient,
+            target_mint: &str,
+            start_unix: i64,
+            end_unix: i64,
+        ) -> Result<usize, String> {
+            if self.pool.is_none() {
+                return Err("Persistence is disabled (no database_url configured)".to_string());
+            }
+
+            let target_pubkey = Pubkey::from_str(target_mint)
+                .map_err(|e| format!("Invalid target mint: {}", e))?;
+
+            let mut before: Option<Signature> = None;
+            let mut replayed = 0usize;
+
+            'paging: loop {
+                let page_config = GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(1000),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                };
+                let signatures = rpc_client
+                    .get_signatures_for_address_with_config(&target_pubkey, page_config)
+                    .map_err(|e| format!("Failed to list signatures for {}: {}", target_pubkey, e))?;
+
+                if signatures.is_empty() {
+                    break;
+                }
+
+                for entry in &signatures {
+                    before = Signature::from_str(&entry.signature).ok();
+
+                    let block_time = match entry.block_time {
+                        Some(t) => t,
+                        None => continue,
+                    };
+                    if block_time > end_unix {
+                        continue;
+                    }
+                    if block_time < start_unix {
+                        break 'paging;
+                    }
+
+                    let Ok(signature) = Signature::from_str(&entry.signature) else { continue };
+                    let transaction = match rpc_client.get_transaction(&signature, UiTransactionEncoding::JsonParsed) {
+                        Ok(tx) => tx,
+                        Err(_) => continue,
+                    };
+
+                    if let Some(trade) = Self::extract_trade(&signature, block_time, &transaction) {
+                        self.record_trade(&trade).await?;
+                        replayed += 1;
+                    }
+                }
+            }
+
+            Ok(replayed)
+        }
+
+        /// Best-effort trade reconstruction from a confirmed transaction's
+        /// token-balance deltas: the largest-magnitude balance change
+        /// across the transaction's post/pre token balances is taken as
+        /// the trade. Historical pool state at that slot isn't available
+        /// here, so backfilled rows carry volume/side/trader faithfully
+        /// but leave `price` at 0.0 rather than guessing.
+        fn extract_trade(
+            signature: &Signature,
+            block_time: i64,
+            transaction: &solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta,
+        ) -> Option<TradeRecord> {
+            let meta = transaction.transaction.meta.as_ref()?;
+            let pre_balances = meta.pre_token_balances.clone().unwrap_or_default();
+            let post_balances = meta.post_token_balances.clone().unwrap_or_default();
+
+            let mut best: Option<(f64, String)> = None;
+            for post in &post_balances {
+                let pre_amount = pre_balances.iter()
+                    .find(|p| p.account_index == post.account_index)
+                    .and_then(|p| p.ui_token_amount.ui_amount)
+                    .unwrap_or(0.0);
+                let post_amount = post.ui_token_amount.ui_amount.unwrap_or(0.0);
+                let delta = post_amount - pre_amount;
+                if delta.abs() <= f64::EPSILON {
+                    continue;
+                }
+                let owner = post.owner.clone().unwrap_or_default();
+                if best.as_ref().map(|(d, _)| delta.abs() > d.abs()).unwrap_or(true) {
+                    best = Some((delta, owner));
+                }
+            }
+
+            let (volume_sol, trader) = best?;
+            Some(TradeRecord {
+                signature: signature.to_string(),
+                is_buy: volume_sol > 0.0,
+                price: 0.0,
+                volume_sol: volume_sol.abs(),
+                trader,
+                unix_timestamp: block_time,
+            })
+        }
+    }
+}
+
+/// Per-send overrides for `MarketMaker::submit_transaction`. Build one from
+/// config defaults with `SubmitOptions::from_config` and adjust the fields
+/// that matter for the call site via struct-update syntax, rather than
+/// threading four loose arguments through every signing path.
+#[derive(Debug, Clone)]
+pub struct SubmitOptions {
+    pub skip_preflight: bool,
+    pub commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig,
+    pub max_retries: usize,
+    pub confirmation_timeout: Duration,
+    /// Return the signature as soon as the leader accepts the transaction
+    /// instead of polling for a confirmation status at all.
+    pub fire_and_forget: bool,
+}
+
+impl SubmitOptions {
+    pub fn from_config(config: &MarketMakerConfig) -> Self {
+        Self {
+            skip_preflight: config.submit_skip_preflight,
+            commitment: config.submit_commitment,
+            max_retries: config.submit_max_retries,
+            confirmation_timeout: Duration::from_millis(config.submit_confirmation_timeout_ms),
+            fire_and_forget: false,
+        }
+    }
+}
+
+/// Failure modes for `MarketMaker::submit_transaction`, kept distinct from
+/// the ad-hoc `String` errors elsewhere so callers can tell a transport/RPC
+/// rejection apart from "it just never confirmed in time".
+#[derive(Debug)]
+pub enum SubmitError {
+    Rpc(String),
+    Timeout {
+        signature: Signature,
+        elapsed: Duration,
+    },
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubmitError::Rpc(msg) => write!(f, "{}", msg),
+            SubmitError::Timeout { signature, elapsed } => write!(
+                f,
+                "transaction {} not confirmed after {:?}",
+                signature, elapsed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/// Advanced market maker bot with multi-wallet support and sophisticated randomization
+pub struct MarketMaker {
+    config: MarketMakerConfig,
+    wallet_pool: Arc<Mutex<WalletPool>>,
+    pool_kind: raydium_clmm::PoolKind,
+    logger: Logger,
+    is_running: Arc<tokio::sync::RwLock<bool>>,
+    recent_trades: Arc<Mutex<VecDeque<TradeType>>>,
+    trade_counter: Arc<Mutex<u32>>,
+    current_wallet: Arc<Mutex<Option<Arc<anchor_client::solana_sdk::signature::Keypair>>>>,
+    wallet_change_counter: Arc<Mutex<u32>>,
+    token_activities: Arc<activity_store::ActivityStore>,
+    last_activity_report: Arc<Mutex<Instant>>,
+    price_monitor: GlobalPriceMonitor,
+    dynamic_ratio_manager: GlobalDynamicRatioManager,
+    volume_wave_manager: GlobalVolumeWaveManager,
+    guardian_mode: GlobalGuardianMode,
+    metrics: Arc<metrics::TradeMetrics>,
+    error_tracking: Arc<Mutex<ErrorTracking>>,
+    http_client: reqwest::Client,
+    pending_ledger: Arc<PendingLedger>,
+    trigger_orders: Arc<TriggerOrderManager>,
+    price_oracle: Arc<PriceOracle>,
+    tpu_submitter: Option<Arc<tpu::TpuSubmitter>>,
+    priority_fee_estimator: Arc<priority_fee::PriorityFeeEstimator>,
+    seen_grpc_signatures: Arc<Mutex<SeenSignatures>>,
+    blockhash_provider: Arc<blockhash_provider::BlockhashProvider>,
+    volume_scheduler: volume_schedule::VolumeScheduler,
+    /// Unbounded running total of observed buy volume, tracked separately
+    /// from the capped `token_activities` window so the volume schedule
+    /// has an accurate "actual volume so far" to compare against.
+    cumulative_buy_volume_sol: Arc<Mutex<f64>>,
+    /// Prices third-party trades the gRPC feed observes, since those don't
+    /// come with a `build_raydium_swap`-computed price the way our own
+    /// buys/sells do.
+    pool_price_reader: pool_price::PoolPriceReader,
+    /// Rolls the activity stream into OHLCV candles so reporting isn't
+    /// limited to a single flat average/min/max over the last 60 minutes.
+    candle_aggregator: Arc<candles::CandleAggregator>,
+    /// Optional Postgres persistence; a no-op store when `database_url`
+    /// isn't configured.
+    trade_store: Arc<persistence::TradeStore>,
+    /// Live bid/ask depth, built from executed-trade flow over the same
+    /// gRPC stream `process_grpc_message` already consumes.
+    order_book: Arc<order_book::OrderBook>,
+}
+
+impl MarketMaker {
+    /// Create a new advanced market maker instance
+    pub fn new(config: MarketMakerConfig) -> Result<Self, String> {
+        let wallet_pool = WalletPool::new()?;
+        let wallet_count = wallet_pool.wallet_count();
+        let wallet_pool = Arc::new(Mutex::new(wallet_pool));
+
+        // Auto-detect which Raydium program actually owns the target pool
+        // so the bot trades CPMM or CLMM tokens alike without a config flag.
+        let target_token_mint = Pubkey::from_str(&config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+        let pool_kind = raydium_clmm::detect_pool_kind(&config.app_state.rpc_client, &target_token_mint);
+
+        let logger = Logger::new("[STEALTH-MARKET-MAKER] => ".green().bold().to_string());
+
+        logger.log(format!("🏊 Detected pool venue: {:?}", pool_kind).cyan().to_string());
+
+        logger.log(format!("🎯 Advanced Market Maker initialized with {} wallets", wallet_count).green().bold().to_string());
+
+        // Create price monitor with default threshold of 15%
+        let price_monitor = create_global_price_monitor(0.15);
+        
+        // Create dynamic ratio manager with weekly changes (168 hours)
+        let dynamic_ratio_manager = create_global_dynamic_ratio_manager(0.67, 0.73, 168);
+        
+        // Create volume wave manager with 2 hour active, 6 hour slow cycles
+        let volume_wave_manager = create_global_volume_wave_manager(2, 6);
+        
+        // Create guardian mode with 10% drop threshold
+        let guardian_mode = create_global_guardian_mode(true, 0.10);
+
+        let metrics = Arc::new(metrics::TradeMetrics::new().map_err(|e| format!("Failed to initialize metrics: {}", e))?);
+        metrics.active_wallets.set(wallet_count as i64);
+
+        let price_oracle = Arc::new(PriceOracle::new(Duration::from_millis(config.price_staleness_limit_ms)));
+
+        let tpu_submitter = if config.use_tpu {
+            match tpu::TpuSubmitter::new() {
+                Ok(submitter) => Some(Arc::new(submitter)),
+                Err(e) => {
+                    logger.log(format!("⚠️ Failed to initialize TPU submitter, will fall back to RPC: {}", e).red().to_string());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let priority_fee_estimator = Arc::new(priority_fee::PriorityFeeEstimator::new(
+            config.priority_fee_percentile,
+            config.priority_fee_min_micro_lamports,
+            config.priority_fee_max_micro_lamports,
+            config.priority_fee_ewma_alpha,
+        ));
+
+        let blockhash_provider = Arc::new(blockhash_provider::BlockhashProvider::new(Duration::from_millis(config.blockhash_refresh_interval_ms)));
+
+        let volume_scheduler = match &config.volume_schedule_path {
+            Some(path) => {
+                let scheduler = volume_schedule::VolumeScheduler::load_from_file(path)?;
+                logger.log(format!("📈 Loaded volume schedule from {}", path).cyan().to_string());
+                scheduler
+            }
+            None => volume_schedule::VolumeScheduler::default(),
+        };
+
+        let pool_price_reader = pool_price::PoolPriceReader::new(target_token_mint, pool_kind, Duration::from_secs(5));
+        let candle_aggregator = Arc::new(candles::CandleAggregator::new());
+        let trade_store = Arc::new(persistence::TradeStore::connect(&config)?);
+        let order_book = Arc::new(order_book::OrderBook::new());
+
+        Ok(Self {
+            config,
+            wallet_pool,
+            pool_kind,
+            logger,
+            is_running: Arc::new(tokio::sync::RwLock::new(false)),
+            recent_trades: Arc::new(Mutex::new(VecDeque::with_capacity(20))),
+            trade_counter: Arc::new(Mutex::new(0)),
+            current_wallet: Arc::new(Mutex::new(None)),
+            wallet_change_counter: Arc::new(Mutex::new(0)),
+            token_activities: Arc::new(activity_store::ActivityStore::new()),
+            last_activity_report: Arc::new(Mutex::new(Instant::now())),
+            price_monitor,
+            dynamic_ratio_manager,
+            volume_wave_manager,
+            guardian_mode,
+            metrics,
+            error_tracking: Arc::new(Mutex::new(ErrorTracking::new(3, Duration::from_secs(30), Duration::from_secs(3600)))),
+            http_client: reqwest::Client::new(),
+            pending_ledger: Arc::new(PendingLedger::new(Duration::from_secs(120))),
+            trigger_orders: Arc::new(TriggerOrderManager::new()),
+            price_oracle,
+            tpu_submitter,
+            priority_fee_estimator,
+            seen_grpc_signatures: Arc::new(Mutex::new(SeenSignatures::new(2000))),
+            blockhash_provider,
+            volume_scheduler,
+            cumulative_buy_volume_sol: Arc::new(Mutex::new(0.0)),
+            pool_price_reader,
+            candle_aggregator,
+            trade_store,
+            order_book,
+        })
+    }
+
+    /// Arm a price-threshold trigger order (stop-loss / take-profit). The
+    /// rule survives wallet rotation and preempts the normal trading cadence
+    /// the instant the target price crosses its threshold.
+    pub async fn add_trigger_order(&self, order: TriggerOrder) {
+        self.trigger_orders.add_order(order).await;
+    }
+
+    /// Builds the swap instruction set for the target token through
+    /// whichever Raydium program actually hosts its pool, so trade
+    /// execution never has to special-case CPMM vs CLMM itself.
+    async fn build_raydium_swap(
+        &self,
+        wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>,
+        swap_config: SwapConfig,
+    ) -> Result<(Arc<anchor_client::solana_sdk::signature::Keypair>, Vec<anchor_client::solana_sdk::instruction::Instruction>, f64), String> {
+        match self.pool_kind {
+            raydium_clmm::PoolKind::Cpmm => {
+                let raydium_cpmm = RaydiumCPMM::new(
+                    wallet.clone(),
+                    Some(self.config.app_state.rpc_client.clone()),
+                    Some(self.config.app_state.rpc_nonblocking_client.clone()),
+                );
+                raydium_cpmm.build_swap_from_default_info(swap_config).await
+            }
+            raydium_clmm::PoolKind::Clmm => {
+                let raydium_clmm = raydium_clmm::RaydiumCLMM::new(
+                    wallet.clone(),
+                    Some(self.config.app_state.rpc_client.clone()),
+                    Some(self.config.app_state.rpc_nonblocking_client.clone()),
+                );
+                raydium_clmm.build_swap_from_default_info(swap_config).await
+            }
+        }
+    }
+
+    /// Start the advanced market maker bot
+    pub async fn start(self: Arc<Self>) -> Result<(), String> {
+        {
+            let mut running = self.is_running.write().await;
+            if *running {
+                return Err("Market maker is already running".to_string());
+            }
+            *running = true;
+        }
+
+        self.logger.log("🚀 Starting Advanced Stealth Market Maker...".green().bold().to_string());
+        self.logger.log(format!("Target token: {}", self.config.target_token_mint));
+        self.logger.log(format!("Buy amount ratio: {:.1}% - {:.1}% of wrapped WSOL", 
+            self.config.randomization_config.min_amount_sol * 100.0, 
+            self.config.randomization_config.max_amount_sol * 100.0));
+        self.logger.log(format!("Buy/Sell ratio: {:.0}% buy / {:.0}% sell", 
+            self.config.randomization_config.buy_sell_ratio * 100.0,
+            (1.0 - self.config.randomization_config.buy_sell_ratio) * 100.0));
+        self.logger.log(format!("Wallet rotation: Every {} trades", 
+            self.config.randomization_config.wallet_rotation_frequency));
+        self.logger.log(format!("Max concurrent trades: {}", self.config.max_concurrent_trades));
+
+        // Initialize first wallet
+        {
+            let mut wallet_pool = self.wallet_pool.lock().await;
+            let first_wallet = wallet_pool.get_random_wallet();
+            let mut current_wallet = self.current_wallet.lock().await;
+            *current_wallet = Some(first_wallet.clone());
+            self.logger.log(format!("🔑 Starting with wallet: {}", first_wallet.pubkey()));
+        }
+
+        // Serve Prometheus metrics so the TokenActivityReport data is
+        // continuously scrapeable instead of only dumped to stdout.
+        if let Ok(metrics_addr) = self.config.metrics_addr.parse() {
+            let metrics = self.metrics.clone();
+            tokio::spawn(async move {
+                if let Err(e) = metrics::serve_metrics(metrics, metrics_addr).await {
+                    error!(%e, "metrics server exited");
+                }
+            });
+            info!(addr = %self.config.metrics_addr, "Prometheus metrics server listening");
+        } else {
+            warn!(addr = %self.config.metrics_addr, "invalid metrics_addr, metrics server not started");
+        }
+
+        // Serve the ticker/trades/candles JSON API alongside Prometheus
+        // metrics, so dashboards can poll live state over HTTP too.
+        if let Ok(data_api_addr) = self.config.data_api_addr.parse() {
+            let market_maker = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = data_api::serve(market_maker, data_api_addr).await {
+                    error!(%e, "data API server exited");
+                }
+            });
+            info!(addr = %self.config.data_api_addr, "Data API server listening");
+        } else {
+            warn!(addr = %self.config.data_api_addr, "invalid data_api_addr, data API server not started");
+        }
+
+        // Keep a fresh blockhash cached in the background so every
+        // buy/sell/wrap/unwrap skips the synchronous RPC round-trip that
+        // used to sit right before signing.
+        {
+            let blockhash_provider = self.blockhash_provider.clone();
+            let app_state = self.config.app_state.clone();
+            tokio::spawn(async move {
+                blockhash_provider.run_refresh_loop(app_state).await;
+            });
+        }
+
+        // Periodically drop pending-trade entries that never got a GRPC
+        // confirmation (dropped tx, expired blockhash) so they don't linger
+        // in the effective-balance calculation forever.
+        {
+            let pending_ledger = self.pending_ledger.clone();
+            tokio::spawn(async move {
+                loop {
+                    time::sleep(Duration::from_secs(30)).await;
+                    pending_ledger.sweep_expired().await;
+                }
+            });
+        }
+
+        // When TPU submission is active, periodically drop in-flight entries
+        // whose blockhash has expired and publish the rolling TPU send rate
+        // (sends per second, confirmed or not).
+        if let Some(submitter) = self.tpu_submitter.clone() {
+            let app_state = self.config.app_state.clone();
+            let metrics = self.metrics.clone();
+            tokio::spawn(async move {
+                loop {
+                    time::sleep(Duration::from_secs(5)).await;
+                    if let Ok(height) = app_state.rpc_client.get_block_height() {
+                        submitter.sweep_expired(height).await;
+                    }
+                    metrics.tpu_transactions_per_second.set(submitter.transactions_per_second().await);
+                }
+            });
+        }
+
+        // Start GRPC streaming for token monitoring
+        let grpc_task = self.start_grpc_monitoring();
+
+        // Start the unified trading engine
+        let trading_task = self.start_advanced_trading_engine();
+
+        // Run all tasks concurrently
+        tokio::select! {
+            result = grpc_task => {
+                if let Err(e) = result {
+                    self.logger.log(format!("GRPC monitoring failed: {}", e).red().to_string());
+                }
+            }
+            result = trading_task => {
+                if let Err(e) = result {
+                    self.logger.log(format!("Trading engine failed: {}", e).red().to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop the market maker bot
+    pub async fn stop(&self) {
+        let mut running = self.is_running.write().await;
+        *running = false;
+        self.logger.log("Advanced Market Maker stopped".red().to_string());
+    }
+
+    /// Check if the market maker is running
+    pub async fn is_running(&self) -> bool {
+        *self.is_running.read().await
+    }
+
+    /// Advanced trading engine with sophisticated randomization
+    #[instrument(skip(self), fields(target_mint = %self.config.target_token_mint, max_concurrent = self.config.max_concurrent_trades))]
+    async fn start_advanced_trading_engine(&self) -> Result<(), String> {
+        self.logger.log("🎰 Starting Advanced Trading Engine...".cyan().bold().to_string());
+
+        // Bounds how many trades may be in flight at once. Permits are held
+        // for the lifetime of a dispatched trade future and released on
+        // completion, so this is the backpressure mechanism: dispatch simply
+        // blocks once `max_concurrent_trades` trades are outstanding.
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(self.config.max_concurrent_trades.max(1)));
+        // Wallets with a trade currently in flight, so the scheduler never
+        // dispatches two concurrent trades onto the same wallet.
+        let in_flight_wallets: Arc<Mutex<std::collections::HashSet<Pubkey>>> = Arc::new(Mutex::new(std::collections::HashSet::new()));
+        // Dispatch-rate gating (the dynamic-ratio/guardian/volume-wave
+        // interval) is independent from trade completion: a slow
+        // confirmation on one wallet no longer stalls volume on others.
+        let mut dispatched: FuturesUnordered<std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>>> = FuturesUnordered::new();
+
+        while self.is_running().await || !dispatched.is_empty() {
+            if !self.is_running().await {
+                // Draining: stop dispatching new trades, just wait out the
+                // in-flight ones so stop() returns with nothing outstanding.
+                dispatched.next().await;
+                continue;
+            }
+
+            // Trigger orders preempt the normal randomized cadence: if the
+            // target price has crossed a stop-loss/take-profit threshold,
+            // fire the configured action immediately instead of waiting for
+            // the next volume-wave/guardian interval.
+            let fired_triggers = match self.price_oracle.resolve().await {
+                // Only a fresh reading may fire a trigger -- a stale sample
+                // sitting in the oracle shouldn't keep re-arming the same
+                // stop-loss/take-profit every loop tick.
+                Some(sample) => self.trigger_orders.evaluate(sample.price).await,
+                None => Vec::new(),
+            };
+
+            if !fired_triggers.is_empty() {
+                for trigger in fired_triggers {
+                    self.logger.log(format!(
+                        "🎯 Trigger order fired: {:?} {:?} @ threshold {:.8}",
+                        trigger.action, trigger.direction, trigger.price_threshold
+                    ).red().bold().to_string());
+
+                    let wallet = self.current_wallet.lock().await.clone();
+                    let Some(wallet) = wallet else { continue };
+                    // Let a dispatched trade on this wallet finish first so
+                    // the forced trade never runs concurrently with it.
+                    while in_flight_wallets.lock().await.contains(&wallet.pubkey()) {
+                        if dispatched.next().await.is_none() {
+                            break;
+                        }
+                    }
+                    let result = match trigger.action {
+                        TriggerAction::ForceBuy => self.execute_advanced_buy_debug(wallet, trigger.size).await.map(|_| ()),
+                        TriggerAction::ForceSell => self.execute_advanced_sell(wallet, trigger.size).await.map(|_| ()),
+                    };
+                    if let Err(e) = result {
+                        self.logger.log(format!("❌ Trigger order execution failed: {}", e).red().to_string());
+                    }
+                }
+                self.check_and_log_activity_report().await;
+                // Give the chain a moment before re-checking; the manager's
+                // own refire throttle stops a standing crossing from
+                // spamming trades, but we still shouldn't busy-spin here.
+                time::sleep(Duration::from_millis(400)).await;
+                continue;
+            }
+
+            // Determine next trade type based on recent history with dynamic ratio and guardian mode
+            let should_buy = {
+                let recent_trades = self.recent_trades.lock().await;
+                let trades_vec: Vec<TradeType> = recent_trades.iter().copied().collect();
+                let wallet_pool = self.wallet_pool.lock().await;
+
+                // Get current dynamic buy ratio
+                let mut dynamic_ratio_manager = self.dynamic_ratio_manager.lock().await;
+                let mut current_buy_ratio = dynamic_ratio_manager.get_current_buy_ratio();
+
+                // Apply guardian mode bias if active
+                let guardian_mode = self.guardian_mode.lock().await;
+                let guardian_buy_bias = guardian_mode.get_buy_bias();
+                if guardian_buy_bias > 0.0 {
+                    current_buy_ratio = (current_buy_ratio + guardian_buy_bias).min(0.95); // Cap at 95%
+                    self.logger.log(format!(
+                        "🛡️ Guardian mode applying buy bias: +{:.1}% (Total ratio: {:.1}%)",
+                        guardian_buy_bias * 100.0,
+                        current_buy_ratio * 100.0
+                    ).red().to_string());
+                }
+
+                self.metrics.dynamic_buy_ratio.set(current_buy_ratio);
+                self.metrics.guardian_mode_active.set(guardian_mode.is_active() as i64);
+
+                wallet_pool.should_buy_next(&trades_vec, current_buy_ratio)
+            };
+
+            // Check if we need to rotate wallet
+            let should_rotate_wallet = {
+                let wallet_change_counter = self.wallet_change_counter.lock().await;
+                *wallet_change_counter >= self.config.randomization_config.wallet_rotation_frequency
+            };
+
+            if should_rotate_wallet {
+                self.rotate_wallet().await;
+            }
+
+            // Pick a wallet that isn't already trading concurrently and isn't
+            // quarantined for repeated failures.
+            let trading_wallet = {
+                const MAX_DRAW_ATTEMPTS: u32 = 8;
+                let mut wallet_pool = self.wallet_pool.lock().await;
+                let in_flight = in_flight_wallets.lock().await;
+                let error_tracking = self.error_tracking.lock().await;
+                let mut candidate = wallet_pool.get_random_wallet();
+                let mut attempts = 1;
+                while (in_flight.contains(&candidate.pubkey())
+                    || error_tracking.is_quarantined(&candidate.pubkey(), "trade"))
+                    && attempts < MAX_DRAW_ATTEMPTS
+                {
+                    candidate = wallet_pool.get_random_wallet();
+                    attempts += 1;
+                }
+                if error_tracking.is_quarantined(&candidate.pubkey(), "trade") {
+                    warn!(wallet = %candidate.pubkey(), "no healthy wallet found after {} draws, using quarantined wallet anyway", MAX_DRAW_ATTEMPTS);
+                }
+                candidate
+            };
+            // Every draw landed on a wallet that is still trading: wait for an
+            // outstanding trade to finish and pick again rather than put a
+            // second concurrent trade on the same wallet.
+            if in_flight_wallets.lock().await.contains(&trading_wallet.pubkey()) {
+                dispatched.next().await;
+                continue;
+            }
+            {
+                let mut current_wallet = self.current_wallet.lock().await;
+                *current_wallet = Some(trading_wallet.clone());
+            }
+
+            // Acquire a concurrency permit before dispatching: this is the
+            // backpressure point, it simply waits if `max_concurrent_trades`
+            // trades are already outstanding, driving those trades meanwhile
+            // so one of them can finish and release its permit.
+            let permit = loop {
+                tokio::select! {
+                    permit = semaphore.clone().acquire_owned() => break permit.map_err(|e| e.to_string())?,
+                    _ = dispatched.next(), if !dispatched.is_empty() => {}
+                }
+            };
+            let wallet_key = trading_wallet.pubkey();
+            in_flight_wallets.lock().await.insert(wallet_key);
+
+            let sell_percentage = 0.1 + (rand::random::<f64>() * 0.4);
+            let in_flight_wallets_for_task = in_flight_wallets.clone();
+            dispatched.push(Box::pin(async move {
+                let result = if should_buy {
+                    self.execute_advanced_buy_debug(trading_wallet.clone(), 0.0).await.map(|_| ())
+                } else {
+                    self.execute_advanced_sell(trading_wallet.clone(), sell_percentage).await.map(|_| ())
+                };
+
+                {
+                    let mut error_tracking = self.error_tracking.lock().await;
+                    match &result {
+                        Ok(_) => error_tracking.record_success(&wallet_key, "trade"),
+                        Err(_) => error_tracking.record_failure(&wallet_key, "trade"),
+                    }
+                }
+                if let Err(e) = result {
+                    self.logger.log(format!(
+                        "❌ Advanced {} failed: {}", if should_buy { "buy" } else { "sell" }, e
+                    ).red().to_string());
+                }
+
+                in_flight_wallets_for_task.lock().await.remove(&wallet_key);
+                drop(permit);
+            }));
+
+            // Generate next interval with price-based throttling, volume waves, and guardian mode.
+            // This gates the *dispatch rate* only -- it doesn't block on the
+            // trade we just dispatched, so slow confirmations on one wallet
+            // never stall volume on others.
+            let next_interval = {
+                let wallet_pool = self.wallet_pool.lock().await;
+                let price_monitor = self.price_monitor.lock().await;
+                let mut volume_wave_manager = self.volume_wave_manager.lock().await;
+                let guardian_mode = self.guardian_mode.lock().await;
+
+                let base_interval = if should_buy {
+                    self.config.randomization_config.base_buy_interval_ms
+                } else {
+                    self.config.randomization_config.base_sell_interval_ms
+                };
+
+                // Get raw interval with wallet pool randomization
+                let raw_interval = wallet_pool.generate_random_interval(base_interval);
+
+                // Apply price-based throttling
+                let throttling_multiplier = price_monitor.get_throttling_multiplier();
+                let throttled_interval = (raw_interval as f64 * throttling_multiplier) as u64;
+
+                // Apply volume wave patterns
+                let current_phase = volume_wave_manager.get_current_phase();
+                let wave_interval = volume_wave_manager.get_natural_interval(throttled_interval);
+
+                // Apply guardian mode acceleration
+                let guardian_multiplier = guardian_mode.get_frequency_multiplier();
+                let final_interval = (wave_interval as f64 * guardian_multiplier) as u64;
+
+                // Log comprehensive status when multiple systems are active
+                let is_complex = throttling_multiplier != 1.0 || guardian_multiplier != 1.0 || guardian_mode.is_active();
+                if is_complex {
+                    self.logger.log(format!(
+                        "⚡ Complex interval: Phase: {:?} | Price: {:.1}x | Guardian: {:.1}x | Final: {:.1}min",
+                        current_phase,
+                        throttling_multiplier,
+                        guardian_multiplier,
+                        final_interval as f64 / 60000.0
+                    ).cyan().to_string());
+                }
+
+                final_interval
+            };
+
+            if next_interval > 600000 {
+                self.logger.log(format!("🐌 Price throttling active - Next trade in {:.1} minutes", next_interval as f64 / 60000.0).red().to_string());
+            } else {
+                self.logger.log(format!("⏰ Next trade in {:.1} minutes", next_interval as f64 / 60000.0).yellow().to_string());
+            }
+
+            // Check and log activity report if it's time
+            self.check_and_log_activity_report().await;
+
+            // Wait for the next dispatch slot, but drain any trade that
+            // finishes in the meantime instead of blocking on it.
+            let next_dispatch_at = Instant::now() + Duration::from_millis(next_interval);
+            loop {
+                tokio::select! {
+                    _ = time::sleep_until(next_dispatch_at) => break,
+                    _ = dispatched.next(), if !dispatched.is_empty() => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rotate to a new wallet
+    async fn rotate_wallet(&self) {
+        const MAX_DRAW_ATTEMPTS: u32 = 8;
+        let new_wallet = {
+            let mut wallet_pool = self.wallet_pool.lock().await;
+            let error_tracking = self.error_tracking.lock().await;
+            let mut candidate = wallet_pool.get_random_wallet();
+            let mut attempts = 1;
+            while error_tracking.is_quarantined(&candidate.pubkey(), "trade") && attempts < MAX_DRAW_ATTEMPTS {
+                candidate = wallet_pool.get_random_wallet();
+                attempts += 1;
+            }
+            if error_tracking.is_quarantined(&candidate.pubkey(), "trade") {
+                warn!(wallet = %candidate.pubkey(), "no healthy wallet found after {} draws, using quarantined wallet anyway", MAX_DRAW_ATTEMPTS);
+            }
+            candidate
+        };
+
+        {
+            let mut current_wallet = self.current_wallet.lock().await;
+            *current_wallet = Some(new_wallet.clone());
+        }
+
+        {
+            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
+            *wallet_change_counter = 0;
+        }
+
+        self.logger.log(format!("🔄 Rotated to wallet: {}", new_wallet.pubkey()).magenta().to_string());
+    }
+
+    /// Execute an advanced buy transaction with separated steps for debugging
+    /// against `current_wallet`. Taking the wallet explicitly (rather than
+    /// reading a shared `self.current_wallet`) is what lets the trading
+    /// engine run several of these concurrently on distinct wallets.
+    #[instrument(skip(self, current_wallet, amount_sol), fields(trade_type = "buy", target_mint = %self.config.target_token_mint, wallet = %current_wallet.pubkey()))]
+    async fn execute_advanced_buy_debug(&self, current_wallet: Arc<anchor_client::solana_sdk::signature::Keypair>, amount_sol: f64) -> Result<Signature, String> {
+        let start_time = Instant::now();
+
+        let wallet_pubkey = current_wallet.pubkey();
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+
+        // Parse target token mint
+        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
+
+        // Get current SOL balance
+        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
+        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
+
+        self.logger.log(format!("🔍 INITIAL SOL Balance: {:.6} SOL ({} lamports)", sol_balance_f64, sol_balance).cyan().to_string());
+
+        // Check if accounts exist
+        let wsol_exists = self.config.app_state.rpc_client.get_account(&wsol_account).is_ok();
+        let target_token_exists = self.config.app_state.rpc_client.get_account(&target_token_account).is_ok();
+
+        self.logger.log(format!("🔍 Account Status - WSOL exists: {}, Target token exists: {}", wsol_exists, target_token_exists).cyan().to_string());
+
+        // Step 1: Create WSOL account if needed
+        if !wsol_exists {
+            self.logger.log("🔧 Step 1: Creating WSOL account...".yellow().to_string());
+            
+            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                .map_err(|e| format!("Failed to get balance before WSOL creation: {}", e))?;
+            
+            match self.create_wsol_account_only(&current_wallet).await {
+                Ok(()) => {
+                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                        .map_err(|e| format!("Failed to get balance after WSOL creation: {}", e))?;
+                    let cost = balance_before - balance_after;
+                    self.logger.log(format!("✅ Step 1 SUCCESS - WSOL account created. Cost: {:.6} SOL", cost as f64 / 1_000_000_000.0).green().to_string());
+                },
+                Err(e) => {
+                    self.logger.log(format!("❌ Step 1 FAILED - WSOL account creation failed: {}", e).red().to_string());
+                    return Err(format!("Step 1 failed: {}", e));
+                }
+            }
+        } else {
+            self.logger.log("✅ Step 1 SKIPPED - WSOL account already exists".green().to_string());
+        }
+
+        // Step 2: Create target token account if needed
+        if !target_token_exists {
+            self.logger.log("🔧 Step 2: Creating target token account...".yellow().to_string());
+            
+            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                .map_err(|e| format!("Failed to get balance before target token creation: {}", e))?;
+            
+            match self.create_target_token_account(&current_wallet, &target_token_mint).await {
+                Ok(()) => {
+                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                        .map_err(|e| format!("Failed to get balance after target token creation: {}", e))?;
+                    let cost = balance_before - balance_after;
+                    self.logger.log(format!("✅ Step 2 SUCCESS - Target token account created. Cost: {:.6} SOL", cost as f64 / 1_000_000_000.0).green().to_string());
+                },
+                Err(e) => {
+                    self.logger.log(format!("❌ Step 2 FAILED - Target token account creation failed: {}", e).red().to_string());
+                    return Err(format!("Step 2 failed: {}", e));
+                }
+            }
+        } else {
+            self.logger.log("✅ Step 2 SKIPPED - Target token account already exists".green().to_string());
+        }
+
+        // Step 3: Smart SOL/WSOL Balance Management
+        self.logger.log("🔧 Step 3: Smart balance management...".yellow().to_string());
+        
+        let confirmed_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get current balance: {}", e))?;
+        let current_balance = self.pending_ledger.effective_sol_lamports(&wallet_pubkey, confirmed_balance).await;
+        let current_balance_lamports = LamportAmount::from_lamports(current_balance);
+        let current_balance_f64 = current_balance_lamports.to_sol();
+
+        // Get WSOL balance, adjusted for any trade on this wallet that's
+        // still in flight so we don't both wrap/unwrap against the same SOL.
+        let confirmed_wsol_balance = match self.config.app_state.rpc_client.get_account(&wsol_account) {
+            Ok(account) => {
+                match spl_token::state::Account::unpack(&account.data) {
+                    Ok(token_account) => token_account.amount,
+                    Err(_) => 0,
+                }
+            },
+            Err(_) => 0,
+        };
+        let wsol_balance_lamports = LamportAmount::from_lamports(
+            self.pending_ledger.effective_wsol_lamports(&wallet_pubkey, confirmed_wsol_balance).await
+        );
+        let wsol_balance = wsol_balance_lamports.to_sol();
+
+        // Read balance thresholds from config (will get from environment variables)
+        // TODO: Get these from global config - for now use hardcoded values
+        let minimal_balance_for_fee = LamportAmount::from_sol(0.005)?; // Reduced threshold - 0.005 SOL should be enough for fees
+        let minimal_wsol_balance_for_trading = LamportAmount::from_sol(0.001)?; // Will be read from env
+        let critical_sol_threshold = LamportAmount::from_sol(0.003)?; // Critical threshold - below this, definitely need to unwrap
+
+        self.logger.log(format!("💰 Step 3 - SOL: {:.6}, WSOL: {:.6}, Critical SOL: {:.6}, WSOL threshold: {:.6}",
+            current_balance_f64, wsol_balance, critical_sol_threshold.to_sol(), minimal_wsol_balance_for_trading.to_sol()).cyan().to_string());
+
+                if current_balance_lamports > critical_sol_threshold && wsol_balance_lamports > minimal_wsol_balance_for_trading {
+            // Case 1: Sufficient SOL and WSOL - don't wrap, use existing WSOL
+            self.logger.log("✅ Step 3 SKIPPED - Sufficient SOL and WSOL balances, no wrapping needed".green().to_string());
+        } else if current_balance_lamports <= critical_sol_threshold && wsol_balance_lamports > minimal_wsol_balance_for_trading {
+             // Case 2: Low SOL but sufficient WSOL - unwrap some WSOL to SOL for fees
+             // Note: unwrapping also returns rent exemption (~0.00204 SOL), so we can unwrap less
+             let needed_sol = minimal_balance_for_fee.saturating_sub(current_balance_lamports);
+             let rent_exemption_bonus = LamportAmount::from_sol(0.00204)?; // Approximate rent exemption we'll get back
+             let unwrap_floor = LamportAmount::from_sol(0.0001)?; // Minimum 0.0001 WSOL unwrap
+             let unwrap_amount_lamports = needed_sol.saturating_sub(rent_exemption_bonus).max(unwrap_floor);
+             let unwrap_amount = unwrap_amount_lamports.to_sol();
+
+             self.logger.log(format!("🔄 Step 3 - Low SOL, unwrapping {:.6} WSOL to SOL for fees (will get ~{:.6} SOL total)",
+                 unwrap_amount, unwrap_amount_lamports.checked_add(rent_exemption_bonus)?.to_sol()).yellow().to_string());
+
+             if wsol_balance_lamports >= unwrap_amount_lamports {
+                let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                    .map_err(|e| format!("Failed to get balance before unwrap: {}", e))?;
+
+                match self.unwrap_wsol_to_sol(&current_wallet, unwrap_amount).await {
+                    Ok(()) => {
+                        let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                            .map_err(|e| format!("Failed to get balance after unwrap: {}", e))?;
+                        let gained = balance_after - balance_before;
+                        self.logger.log(format!("✅ Step 3 SUCCESS - WSOL unwrapped to SOL. Amount: {:.6} WSOL, SOL gained: {:.6}",
+                            unwrap_amount, gained as f64 / 1_000_000_000.0).green().to_string());
+                    },
+                    Err(e) => {
+                        self.logger.log(format!("❌ Step 3 FAILED - WSOL unwrapping failed: {}", e).red().to_string());
+                        return Err(format!("Step 3 failed: {}", e));
+                    }
+                }
+            } else {
+                return Err(format!("Insufficient WSOL for unwrapping. Need: {:.6}, Have: {:.6}", unwrap_amount, wsol_balance));
+            }
+        } else {
+            // Case 3: Need to wrap SOL to WSOL (original logic)
+            let reserve_for_fees = LamportAmount::from_sol(0.0005)?; // Reserve for transaction fees
+            let available_sol_lamports = current_balance_lamports.saturating_sub(reserve_for_fees);
+
+            if available_sol_lamports == LamportAmount::ZERO {
+                return Err(format!("Insufficient SOL for wrapping. Current: {:.6}, Reserved: {:.6}", current_balance_f64, reserve_for_fees.to_sol()));
+            }
+
+            let wrap_amount_lamports = available_sol_lamports.checked_mul_bps(7_500)?; // Use 75% of available SOL
+            let wrap_amount = wrap_amount_lamports.to_sol();
+
+            self.logger.log(format!("🔧 Step 3 - Wrapping {:.6} SOL to WSOL", wrap_amount).yellow().to_string());
+
+            let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                .map_err(|e| format!("Failed to get balance before wrap: {}", e))?;
+
+            match self.wrap_sol_to_wsol(&current_wallet, wrap_amount).await {
+                Ok(()) => {
+                    let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                        .map_err(|e| format!("Failed to get balance after wrap: {}", e))?;
+                    let cost = balance_before - balance_after;
+                    self.logger.log(format!("✅ Step 3 SUCCESS - SOL wrapped to WSOL. Amount: {:.6} SOL, Total cost: {:.6} SOL",
+                        wrap_amount, cost as f64 / 1_000_000_000.0).green().to_string());
+                },
+                Err(e) => {
+                    self.logger.log(format!("❌ Step 3 FAILED - SOL wrapping failed: {}", e).red().to_string());
+                    return Err(format!("Step 3 failed: {}", e));
+                }
+            }
+        }
+
+        // Step 4: Execute swap
+        self.logger.log("🔧 Step 4: Executing swap...".yellow().to_string());
+
+        // Get WSOL balance after balance management
+        let wsol_balance_after_management_lamports = match self.config.app_state.rpc_client.get_account(&wsol_account) {
+            Ok(account) => {
+                match spl_token::state::Account::unpack(&account.data) {
+                    Ok(token_account) => LamportAmount::from_lamports(token_account.amount),
+                    Err(_) => LamportAmount::ZERO,
+                }
+            },
+            Err(_) => LamportAmount::ZERO,
+        };
+        let wsol_balance_after_management = wsol_balance_after_management_lamports.to_sol();
+
+        if wsol_balance_after_management_lamports == LamportAmount::ZERO {
+            return Err("No WSOL balance available for swap".to_string());
+        }
+
+        // Calculate buy amount based on current WSOL balance (after smart management)
+        let mut rng = rand::thread_rng();
+        let random_multiplier = self.config.randomization_config.min_amount_sol +
+            (self.config.randomization_config.max_amount_sol - self.config.randomization_config.min_amount_sol) * rng.gen::<f64>();
+        let mut final_buy_amount_lamports = wsol_balance_after_management_lamports.checked_mul_ratio(random_multiplier)?;
+        let mut final_buy_amount = final_buy_amount_lamports.to_sol();
+        // A forced buy (trigger order) names its own SOL size; the trading
+        // loop passes 0.0 and keeps the randomized size above.
+        if amount_sol > 0.0 {
+            final_buy_amount_lamports = LamportAmount::from_sol(amount_sol)?;
+            final_buy_amount = final_buy_amount_lamports.to_sol();
+        }
+
+        // If an operator-supplied volume schedule is configured, cap this
+        // buy so realized cumulative volume tracks the schedule instead of
+        // dumping the whole WSOL balance at once.
+        if !self.volume_scheduler.is_empty() {
+            let now_unix = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|e| format!("System clock is before the Unix epoch: {}", e))?
+                .as_secs() as i64;
+            let realized_volume_sol = *self.cumulative_buy_volume_sol.lock().await;
+
+            let ceiling_sol = self.volume_scheduler
+                .remaining_ceiling_sol(now_unix, realized_volume_sol)
+                .unwrap_or(final_buy_amount);
+
+            if ceiling_sol <= 0.0 {
+                self.logger.log(format!(
+                    "⏸️ Volume schedule: already at/ahead of target ({:.6} SOL realized), skipping this stealth buy",
+                    realized_volume_sol
+                ).yellow().to_string());
+                return Err("Ahead of volume schedule, skipping buy".to_string());
+            }
+
+            if final_buy_amount > ceiling_sol {
+                self.logger.log(format!(
+                    "📉 Volume schedule: capping stealth buy from {:.6} to {:.6} SOL (realized {:.6})",
+                    final_buy_amount, ceiling_sol, realized_volume_sol
+                ).yellow().to_string());
+                final_buy_amount = ceiling_sol;
+                final_buy_amount_lamports = LamportAmount::from_sol(final_buy_amount)?;
+            }
+        }
+
+        // This venue has no resting limit-order book to post against, so
+        // "post within X bps of mid" doesn't map onto a single swap-sizing
+        // amount; instead we use the depth-proxy order book to skip buys
+        // while the market is abnormally wide.
+        if let (Some(spread), Some(mid_price)) = (self.order_book.spread().await, self.order_book.mid_price().await) {
+            if mid_price > 0.0 {
+                let spread_bps = (spread / mid_price) * 10_000.0;
+                if spread_bps > self.config.max_quote_spread_bps {
+                    self.logger.log(format!(
+                        "⏸️ Spread too wide: {:.1} bps (mid {:.8}) exceeds max_quote_spread_bps {:.1}, skipping stealth buy",
+                        spread_bps, mid_price, self.config.max_quote_spread_bps
+                    ).yellow().to_string());
+                    return Err("Order book spread too wide, skipping buy".to_string());
+                }
+            }
+        }
+
+        self.logger.log(format!("🎯 Step 4 - WSOL Balance: {:.6}, Multiplier: {:.3}, Buy Amount: {:.6} SOL",
+            wsol_balance_after_management, random_multiplier, final_buy_amount).cyan().to_string());
+
+        // Create swap configuration
+        let swap_config = SwapConfig {
+            mint: self.config.target_token_mint.clone(),
+            swap_direction: SwapDirection::Buy,
+            in_type: SwapInType::Qty,
+            amount_in: final_buy_amount,
+            slippage: self.config.slippage,
+            max_buy_amount: final_buy_amount,
+        };
+
+        // Build and execute swap
+        let balance_before = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get balance before swap: {}", e))?;
+
+        match self.build_raydium_swap(&current_wallet, swap_config).await {
+            Ok((keypair, raydium_instructions, token_price)) => {
+                self.logger.log(format!("Token price: ${:.8}", token_price));
+                self.price_oracle.record(PriceSourceKind::PoolReserves, token_price, 0.9).await;
+
+                // Widen liquidity sourcing: check whether Jupiter v6 quotes a
+                // better out_amount than the Raydium pool for the same input,
+                // bounded by a hard timeout so a slow aggregator never stalls
+                // the trading loop. Jupiter quotes base units, so the Raydium
+                // estimate is scaled by the mint's decimals to compare.
+                let amount_in_lamports = final_buy_amount_lamports.lamports();
+                let target_decimals = self.config.app_state.rpc_client.get_account(&target_token_mint).ok()
+                    .and_then(|account| spl_token::state::Mint::unpack(&account.data).ok())
+                    .map(|mint| mint.decimals)
+                    .unwrap_or(0);
+                let base_units_per_token = 10f64.powi(target_decimals as i32);
+                let raydium_out_amount = (final_buy_amount / token_price.max(f64::MIN_POSITIVE) * base_units_per_token) as u64;
+                let jupiter_route = jupiter_route::fetch_route(
+                    &self.http_client,
+                    &spl_token::native_mint::id(),
+                    &target_token_mint,
+                    amount_in_lamports,
+                    self.config.slippage,
+                    &wallet_pubkey,
+                    Duration::from_millis(self.config.jupiter_quote_timeout_ms),
+                ).await;
+
+                let instructions = match jupiter_route {
+                    Some(route) if route.out_amount > raydium_out_amount => {
+                        self.logger.log(format!(
+                            "🪐 Routing buy through Jupiter v6 ({} > {} out)", route.out_amount, raydium_out_amount
+                        ).cyan().to_string());
+                        let implied_price = final_buy_amount / (route.out_amount.max(1) as f64 / base_units_per_token);
+                        self.price_oracle.record(PriceSourceKind::AggregatorQuote, implied_price, 0.6).await;
+                        route.instructions
+                    }
+                    Some(_) | None => raydium_instructions,
+                };
+
+                // Bid a realistic compute-unit price for the pool/ATA
+                // accounts this transaction write-locks so it doesn't sit
+                // at base fee and get skipped during a volume burst.
+                let unit_price = self.priority_fee_estimator
+                    .estimate_unit_price(&self.config.app_state, &[wsol_account, target_token_account])
+                    .await;
+                let mut instructions_with_fee = priority_fee::PriorityFeeEstimator::build_instructions(unit_price, instructions.len());
+                instructions_with_fee.extend(instructions);
+                let instructions = instructions_with_fee;
+
+                // Send through the same TPU-vs-RPC path (and submit_* config
+                // knobs) the sell side already uses, instead of duplicating
+                // that branching here against the legacy skip-simulation
+                // sender.
+                let send_result = self.send_transaction(&keypair, instructions).await;
+
+                match send_result {
+                    Ok(signature) => {
+                        self.pending_ledger.publish(PendingTrade {
+                            signature,
+                            wallet: wallet_pubkey,
+                            sol_delta_lamports: 0,
+                            wsol_delta_lamports: -((final_buy_amount * 1_000_000_000.0) as i64),
+                            token_delta: 0,
+                            submitted_at: Instant::now(),
+                        }).await;
+
+                        let balance_after = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+                            .map_err(|e| format!("Failed to get balance after swap: {}", e))?;
+                        let cost = balance_before - balance_after;
+                        
+                        self.logger.log(format!("✅ Step 4 SUCCESS - Swap executed. Amount: {:.6} SOL, Cost: {:.6} SOL, Signature: {}", 
+                            final_buy_amount, cost as f64 / 1_000_000_000.0, signature).green().to_string());
+                        
+                        // Update trade tracking
+                        {
+                            let mut recent_trades = self.recent_trades.lock().await;
+                            recent_trades.push_back(TradeType::Buy);
+                            if recent_trades.len() > 20 {
+                                recent_trades.pop_front();
+                            }
+                        }
+
+                        {
+                            let mut trade_counter = self.trade_counter.lock().await;
+                            *trade_counter += 1;
+                        }
+
+                        {
+                            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
+                            *wallet_change_counter += 1;
+                        }
+
+                        self.logger.log(format!(
+                            "🎉 DEBUG BUY COMPLETED! Total time: {:?}",
+                            start_time.elapsed()
+                        ).green().bold().to_string());
+
+                        self.metrics.trades_total.inc();
+                        self.metrics.buy_volume_sol.inc_by(final_buy_amount);
+                        info!(elapsed = ?start_time.elapsed(), %signature, "buy trade confirmed");
+
+                        Ok(signature)
+                    },
+                    Err(e) => {
+                        self.logger.log(format!("❌ Step 4 FAILED - ON-CHAIN transaction failed (this is the real error): {}", e).red().to_string());
+                        self.metrics.failed_transactions.inc();
+                        error!(error = %e, "buy trade failed on-chain");
+                        Err(format!("Step 4 failed: {}", e))
+                    }
+                }
+            },
+            Err(e) => {
+                self.logger.log(format!("❌ Step 4 FAILED - Swap building failed: {}", e).red().to_string());
+                self.metrics.failed_transactions.inc();
+                error!(error = %e, "buy swap build failed");
+                Err(format!("Step 4 failed: {}", e))
+            }
+        }
+    }
+
+    /// Execute an advanced buy transaction with the current wallet
+    async fn execute_advanced_buy(&self, _amount_sol: f64) -> Result<Signature, String> {
+        let start_time = Instant::now();
+        
+        let current_wallet = {
+            let current_wallet = self.current_wallet.lock().await;
+            current_wallet.clone().ok_or("No current wallet set")?
+        };
+
+        let wallet_pubkey = current_wallet.pubkey();
+        
+        // Get current SOL balance
+        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
+        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
+        
+        // Check if we have enough SOL for operations
+        if sol_balance_f64 < 0.002 {
+            return Err(format!("Insufficient SOL balance: {} SOL", sol_balance_f64));
+        }
+        
+        // Calculate amount to wrap to WSOL (85% of available SOL, keeping 15% for fees)
+        let fee_reserve = 0.0015; // Reserve for transaction fees
+        let available_sol = sol_balance_f64 - fee_reserve;
+        let wrap_amount = if available_sol > 0.0 {
+            available_sol * 0.85 // Wrap 85% of available SOL
+        } else {
+            return Err("Insufficient SOL for wrapping".to_string());
+        };
+        
+        // Calculate buy amount based on WSOL balance (after wrapping, WSOL balance = wrap_amount)
+        // Apply randomization ratio directly to the WSOL balance
+        let wsol_balance_after_wrap = wrap_amount; // This will be the WSOL balance after wrapping
+        
+        // Get ratio range from config (these are ratios between 0 and 1)
+        let min_ratio = self.config.randomization_config.min_amount_sol.max(0.1).min(1.0);
+        let max_ratio = self.config.randomization_config.max_amount_sol.max(min_ratio).min(1.0);
+        
+        let mut rng = rand::thread_rng();
+        let random_multiplier = min_ratio + (max_ratio - min_ratio) * rng.gen::<f64>();
+        let final_buy_amount = wsol_balance_after_wrap * random_multiplier *0.1; // for me to see what happend 
+        
+        // Get WSOL and target token account addresses
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
+        
+        // Check if accounts exist
+        let wsol_exists = self.config.app_state.rpc_client.get_account(&wsol_account).is_ok();
+        let target_token_exists = self.config.app_state.rpc_client.get_account(&target_token_account).is_ok();
+        
+        // Start building instructions
+        let mut instructions = Vec::new();
+        
+        // Create WSOL account if needed
+        if !wsol_exists {
+            let create_wsol_instruction = spl_associated_token_account::instruction::create_associated_token_account(
+                &wallet_pubkey,  // payer
+                &wallet_pubkey,  // owner
+                &spl_token::native_mint::id(), // mint
+                &spl_token::id(), // token program
+            );
+            instructions.push(create_wsol_instruction);
+            self.logger.log("🔧 Added WSOL account creation instruction".yellow().to_string());
+        }
+        
+        // Create target token account if needed
+        if !target_token_exists {
+            let create_target_token_instruction = spl_associated_token_account::instruction::create_associated_token_account(
+                &wallet_pubkey,  // payer
+                &wallet_pubkey,  // owner
+                &target_token_mint, // mint
+                &spl_token::id(), // token program
+            );
+            instructions.push(create_target_token_instruction);
+            self.logger.log("🔧 Added target token account creation instruction".yellow().to_string());
+        }
+        
+        // Wrap SOL to WSOL
+        let wrap_lamports = (wrap_amount * 1_000_000_000.0) as u64;
+        instructions.push(
+            system_instruction::transfer(
+                &wallet_pubkey,
+                &wsol_account,
+                wrap_lamports,
+            )
+        );
+        instructions.push(
+            sync_native(&spl_token::id(), &wsol_account)
+                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
+        );
+        
+        self.logger.log(format!("💰 SOL Balance: {:.6}, Available: {:.6}, Wrap: {:.6} SOL", 
+            sol_balance_f64, available_sol, wrap_amount).cyan().to_string());
+        self.logger.log(format!("🎯 Buy calculation: WSOL({:.6}) * {:.3} = {:.6} SOL", 
+            wsol_balance_after_wrap, random_multiplier, final_buy_amount).cyan().to_string());
+        self.logger.log(format!("🔥 STEALTH BUY - Wrap: {:.6} SOL, Buy: {:.6} SOL - Wallet: {}", 
+            wrap_amount, final_buy_amount, wallet_pubkey).green().bold().to_string());
+        
+        // Create swap configuration
+        let swap_config = SwapConfig {
+            mint: self.config.target_token_mint.clone(),
+            swap_direction: SwapDirection::Buy,
+            in_type: SwapInType::Qty,
+            amount_in: final_buy_amount,
+            slippage: self.config.slippage,
+            max_buy_amount: final_buy_amount,
+        };
+
+        // Build swap instructions only (not the full transaction)
+        let (_, swap_instructions, token_price) = self
+            .build_raydium_swap(&current_wallet, swap_config)
+            .await
+            .map_err(|e| format!("Failed to build buy transaction: {}", e))?;
+
+        self.logger.log(format!("Token price: ${:.8}", token_price));
+        self.price_oracle.record(PriceSourceKind::PoolReserves, token_price, 0.9).await;
+
+        // Add swap instructions to our combined transaction
+        instructions.extend(swap_instructions);
+
+        // Bid a realistic compute-unit price up front so this combined
+        // transaction doesn't sit at base fee and get skipped during a
+        // volume burst.
+        let unit_price = self.priority_fee_estimator
+            .estimate_unit_price(&self.config.app_state, &[wsol_account, target_token_account])
+            .await;
+        let mut instructions_with_fee = priority_fee::PriorityFeeEstimator::build_instructions(unit_price, instructions.len());
+        instructions_with_fee.extend(instructions);
+        let instructions = instructions_with_fee;
+
+        // Send the combined transaction
+        let recent_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?.blockhash;
+
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&wallet_pubkey),
+            &[current_wallet.as_ref()],
+            recent_blockhash,
+        );
+
+        // Stealth buys race other bots to land, so skip preflight and
+        // don't wait around for confirmation here.
+        let submit_opts = SubmitOptions {
+            skip_preflight: true,
+            fire_and_forget: true,
+            ..SubmitOptions::from_config(&self.config)
+        };
+        let signature = self.submit_transaction(&transaction, submit_opts).await
+            .map_err(|e| format!("Failed to send combined transaction: {}", e))?;
+
+        // Update trade tracking
+        {
+            let mut recent_trades = self.recent_trades.lock().await;
+            recent_trades.push_back(TradeType::Buy);
+            if recent_trades.len() > 20 {
+                recent_trades.pop_front();
+            }
+        }
+
+        {
+            let mut trade_counter = self.trade_counter.lock().await;
+            *trade_counter += 1;
+        }
+
+        {
+            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
+            *wallet_change_counter += 1;
+        }
+
+        self.logger.log(format!(
+            "✅ STEALTH BUY SUCCESS! Wrapped: {:.6} SOL → WSOL, Used: {:.6} SOL ({:.1}%), Signature: {}, Time: {:?}",
+            wrap_amount, final_buy_amount, (final_buy_amount / wrap_amount * 100.0), signature, start_time.elapsed()
+        ).green().bold().to_string());
+
+        Ok(signature)
+    }
+
+    /// Execute an advanced sell transaction with the current wallet
+    #[instrument(skip(self, current_wallet), fields(trade_type = "sell", target_mint = %self.config.target_token_mint, wallet = %current_wallet.pubkey()))]
+    async fn execute_advanced_sell(&self, current_wallet: Arc<anchor_client::solana_sdk::signature::Keypair>, percentage: f64) -> Result<Signature, String> {
+        let start_time = Instant::now();
+
+        // Check and prepare wallet (SOL, WSOL, Token balances)
+        if let Err(e) = self.check_and_prepare_wallet(&current_wallet).await {
+            self.metrics.failed_transactions.inc();
+            error!(error = %e, "wallet preparation failed before sell");
+            return Err(e);
+        }
+
+        // Log wallet and WSOL account before trading
+        let wsol_account = get_associated_token_address(&current_wallet.pubkey(), &spl_token::native_mint::id());
+        self.logger.log(format!("🔥 STEALTH SELL - Percentage: {:.1}% - Wallet: {} - WSOL: {}", 
+            percentage * 100.0, current_wallet.pubkey(), wsol_account).blue().bold().to_string());
+
+        let swap_config = SwapConfig {
+            mint: self.config.target_token_mint.clone(),
+            swap_direction: SwapDirection::Sell,
+            in_type: SwapInType::Pct,
+            amount_in: percentage,
+            slippage: self.config.slippage,
+            max_buy_amount: 0.0,
+        };
+
+        // Build swap transaction
+        let (keypair, instructions, token_price) = self
+            .build_raydium_swap(&current_wallet, swap_config)
+            .await
+            .map_err(|e| {
+                self.metrics.failed_transactions.inc();
+                error!(error = %e, "sell swap build failed");
+                format!("Failed to build sell transaction: {}", e)
+            })?;
+
+        self.logger.log(format!("Token price: ${:.8}", token_price));
+        self.price_oracle.record(PriceSourceKind::PoolReserves, token_price, 0.9).await;
+
+        // Bid a realistic compute-unit price for the pool/ATA accounts this
+        // transaction write-locks so it doesn't sit at base fee and get
+        // skipped during a volume burst.
+        let unit_price = self.priority_fee_estimator
+            .estimate_unit_price(&self.config.app_state, &[wsol_account])
+            .await;
+        let mut instructions_with_fee = priority_fee::PriorityFeeEstimator::build_instructions(unit_price, instructions.len());
+        instructions_with_fee.extend(instructions);
+        let instructions = instructions_with_fee;
+
+        // Send transaction
+        let signature = self.send_transaction(&keypair, instructions).await
+            .map_err(|e| {
+                self.metrics.failed_transactions.inc();
+                error!(error = %e, "sell transaction send failed");
+                format!("Failed to send sell transaction: {}", e)
+            })?;
+
+        self.pending_ledger.publish(PendingTrade {
+            signature,
+            wallet: current_wallet.pubkey(),
+            sol_delta_lamports: 0,
+            wsol_delta_lamports: 0,
+            token_delta: 0,
+            submitted_at: Instant::now(),
+        }).await;
+
+        // Update trade tracking
+        {
+            let mut recent_trades = self.recent_trades.lock().await;
+            recent_trades.push_back(TradeType::Sell);
+            if recent_trades.len() > 20 {
+                recent_trades.pop_front();
+            }
+        }
+
+        {
+            let mut trade_counter = self.trade_counter.lock().await;
+            *trade_counter += 1;
+        }
+
+        {
+            let mut wallet_change_counter = self.wallet_change_counter.lock().await;
+            *wallet_change_counter += 1;
+        }
+
+        self.logger.log(format!(
+            "✅ STEALTH SELL SUCCESS! Percentage: {:.1}%, Signature: {}, Time: {:?}",
+            percentage * 100.0, signature, start_time.elapsed()
+        ).blue().bold().to_string());
+
+        self.metrics.trades_total.inc();
+        info!(elapsed = ?start_time.elapsed(), %signature, percentage, "sell trade confirmed");
+
+        Ok(signature)
+    }
+
+    /// Start GRPC monitoring for the target token
+    /// Runs one worker per configured gRPC endpoint concurrently. Each
+    /// worker reconnects and re-subscribes with exponential backoff on its
+    /// own failures instead of ending the whole monitor, so the bot only
+    /// goes blind if every endpoint is down at once. Only returns once
+    /// `is_running()` goes false.
+    async fn start_grpc_monitoring(&self) -> Result<(), String> {
+        let mut endpoints = vec![(self.config.yellowstone_grpc_http.clone(), self.config.yellowstone_grpc_token.clone())];
+        endpoints.extend(self.config.additional_grpc_endpoints.clone());
+
+        self.logger.log(format!(
+            "🔍 Starting GRPC token monitoring across {} endpoint(s)...", endpoints.len()
+        ).cyan().to_string());
+
+        let workers = endpoints
+            .into_iter()
+            .enumerate()
+            .map(|(index, (http, token))| self.run_grpc_endpoint(index, http, token));
+
+        futures_util::future::join_all(workers).await;
+        Ok(())
+    }
+
+    /// Keeps a single gRPC endpoint connected for as long as the bot is
+    /// running, reconnecting with exponential backoff whenever the
+    /// connection, subscription, or stream fails.
+    async fn run_grpc_endpoint(&self, endpoint_index: usize, http: String, token: String) {
+        let mut backoff = Duration::from_secs(2);
+        const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+        // Unix time the current outage started, if one is in progress. Set
+        // the first time a connection attempt fails after a working stream,
+        // and handed to the next successful connect so it can flag the
+        // affected candle buckets as an incomplete-data gap.
+        let mut gap_started_unix: Option<i64> = None;
+
+        while self.is_running().await {
+            match self.connect_and_stream_grpc_endpoint(endpoint_index, &http, &token, &mut gap_started_unix).await {
+                Ok(()) => {
+                    // Either a clean shutdown or the stream ended on its own;
+                    // either way reset the backoff for any future reconnect.
+                    backoff = Duration::from_secs(2);
+                }
+                Err(e) => {
+                    if gap_started_unix.is_none() {
+                        gap_started_unix = Some(current_unix_timestamp());
+                    }
+                    self.logger.log(format!(
+                        "[GRPC #{}] {} disconnected: {}. Reconnecting in {:?}...",
+                        endpoint_index, http, e, backoff
+                    ).red().to_string());
+                    time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            }
+
+            if !self.is_running().await {
+                break;
+            }
+            // Brief pause before resubscribing even on a clean end, so a
+            // flapping endpoint can't spin the reconnect loop hot.
+            time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Connects, subscribes, and processes messages from a single endpoint
+    /// until the stream ends or the bot stops. Runs its own heartbeat via
+    /// `tokio::select!` in the same loop as message processing, so a
+    /// reconnect never leaves a stale heartbeat task running against a
+    /// dead connection. Also watches the time since the last message
+    /// arrived, tearing the stream down for a fresh reconnect if it goes
+    /// quiet past `grpc_stream_staleness_ms` even without an explicit error.
+    /// `gap_started_unix`, when set, is the time the previous attempt on
+    /// this endpoint went down; once reconnected it's taken and used to flag
+    /// the outage window in the candle aggregator as an incomplete-data gap.
+    /// An attempt that fails before connecting leaves it in place.
+    async fn connect_and_stream_grpc_endpoint(
+        &self,
+        endpoint_index: usize,
+        http: &str,
+        token: &str,
+        gap_started_unix: &mut Option<i64>,
+    ) -> Result<(), String> {
+        let mut client = GeyserGrpcClient::build_from_shared(http.to_string())
+            .map_err(|e| format!("Failed to build GRPC client: {}", e))?
+            .x_token::<String>(Some(token.to_string()))
+            .map_err(|e| format!("Failed to set x_token: {}", e))?
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(|e| format!("Failed to set tls config: {}", e))?
+            .connect()
+            .await
+            .map_err(|e| format!("Failed to connect to GRPC: {}", e))?;
+
+        let (subscribe_tx, mut stream) = client.subscribe().await
+            .map_err(|e| format!("Failed to subscribe: {}", e))?;
+
+        let subscribe_tx = Arc::new(tokio::sync::Mutex::new(subscribe_tx));
+
+        // Set up subscription for target token
+        let subscription_request = SubscribeRequest {
+            transactions: maplit::hashmap! {
+                "TargetToken".to_owned() => SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    failed: Some(false),
+                    signature: None,
+                    // Watch both Raydium venues so monitoring keeps working
+                    // regardless of which program the target pool settled on.
+                    account_include: vec![
+                        raydium_clmm::CPMM_PROGRAM_ID.to_string(),
+                        raydium_clmm::CLMM_PROGRAM_ID.to_string(),
+                    ],
+                    account_exclude: vec![JUPITER_PROGRAM.to_string(), OKX_DEX_PROGRAM.to_string()],
+                    account_required: Vec::<String>::new(),
+                }
+            },
+            commitment: Some(CommitmentLevel::Processed as i32),
+            ..Default::default()
+        };
+
+        subscribe_tx
+            .lock()
+            .await
+            .send(subscription_request)
+            .await
+            .map_err(|e| format!("Failed to send subscribe request: {}", e))?;
+
+        self.logger.log(format!("✅ [GRPC #{}] {} connected, processing transactions...", endpoint_index, http).green().to_string());
+
+        if let Some(gap_start) = gap_started_unix.take() {
+            let gap_end = current_unix_timestamp();
+            self.candle_aggregator.mark_gap(gap_start, gap_end).await;
+            self.logger.log(format!(
+                "⚠️ [GRPC #{}] Reconnected after a ~{}s data gap; affected candle buckets marked incomplete",
+                endpoint_index, (gap_end - gap_start).max(0)
+            ).yellow().to_string());
+        }
+
+        let mut heartbeat_interval = time::interval(Duration::from_secs(30));
+        heartbeat_interval.tick().await; // first tick fires immediately
+        let staleness_limit = Duration::from_millis(self.config.grpc_stream_staleness_ms);
+        let mut last_message_at = Instant::now();
+
+        loop {
+            if !self.is_running().await {
+                return Ok(());
+            }
+
+            tokio::select! {
+                _ = heartbeat_interval.tick() => {
+                    if last_message_at.elapsed() > staleness_limit {
+                        return Err(format!(
+                            "stream stale: no messages received in {:?} (limit {:?})",
+                            last_message_at.elapsed(), staleness_limit
+                        ));
+                    }
+                    send_heartbeat_ping(&subscribe_tx).await
+                        .map_err(|e| format!("heartbeat ping failed: {}", e))?;
+                }
+                msg = stream.next() => {
+                    match msg {
+                        Some(Ok(msg)) => {
+                            last_message_at = Instant::now();
+                            if self.is_duplicate_grpc_message(&msg).await {
+                                continue;
+                            }
+                            if let Err(e) = self.process_grpc_message(&msg).await {
+                                self.logger.log(format!("Error processing message: {}", e).red().to_string());
+                            }
+                        }
+                        Some(Err(e)) => return Err(format!("stream error: {}", e)),
+                        None => return Err("stream ended".to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Dedupes a transaction observed on more than one gRPC endpoint by
+    /// signature, so the same confirmed trade isn't recorded twice in
+    /// `token_activities`/price feeds when two endpoints both deliver it.
+    async fn is_duplicate_grpc_message(&self, msg: &SubscribeUpdate) -> bool {
+        let Some(UpdateOneof::Transaction(txn_info)) = &msg.update_oneof else { return false };
+        let Some(tx) = &txn_info.transaction else { return false };
+        let Ok(signature) = Signature::try_from(tx.signature.as_slice()) else { return false };
+
+        self.seen_grpc_signatures.lock().await.check_and_insert(signature)
+    }
+
+    /// Check and prepare wallet for trading (check balances, create/wrap WSOL if needed)
+    async fn check_and_prepare_wallet(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        
+        // Log current trading wallet
+        self.logger.log(format!("🔍 Current trading wallet: {}", wallet_pubkey).cyan().to_string());
+
+        // Get SOL balance
+        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
+        let sol_balance_f64 = sol_balance as f64 / 1_000_000_000.0;
+        
+        // Get WSOL account address
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        
+        // Log WSOL account
+        self.logger.log(format!("🔍 WSOL account: {}", wsol_account).cyan().to_string());
+        
+        // Check if WSOL account exists and get balance
+        let (wsol_exists, wsol_balance) = match self.config.app_state.rpc_client.get_account(&wsol_account) {
+            Ok(account) => {
+                match spl_token::state::Account::unpack(&account.data) {
+                    Ok(token_account) => {
+                        let balance = token_account.amount as f64 / 1_000_000_000.0;
+                        self.logger.log(format!("💰 WSOL balance: {} SOL", balance).green().to_string());
+                        (true, balance)
+                    },
+                    Err(_) => {
+                        self.logger.log("❌ WSOL account exists but couldn't parse data".red().to_string());
+                        (false, 0.0)
+                    }
+                }
+            },
+            Err(_) => {
+                self.logger.log("❌ WSOL account doesn't exist".red().to_string());
+                (false, 0.0)
+            }
+        };
+
+        // Get target token balance
+        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
+        
+        let (target_token_exists, target_token_balance) = match self.config.app_state.rpc_client.get_account(&target_token_account) {
+            Ok(account) => {
+                match spl_token::state::Account::unpack(&account.data) {
+                    Ok(token_account) => {
+                        let balance = token_account.amount;
+                        self.logger.log(format!("🎯 Target token balance: {}", balance).green().to_string());
+                        (true, balance)
+                    },
+                    Err(_) => {
+                        self.logger.log("❌ Target token account exists but couldn't parse data".red().to_string());
+                        (false, 0)
+                    }
+                }
+            },
+            Err(_) => {
+                self.logger.log("❌ Target token account doesn't exist".red().to_string());
+                (false, 0)
+            }
+        };
+
+        // Log all balances
+        self.logger.log(format!("💰 Wallet balances - SOL: {:.6}, WSOL: {:.6}, Token: {}", 
+            sol_balance_f64, wsol_balance, target_token_balance).purple().to_string());
+
+        // Create WSOL account if it doesn't exist
+        if !wsol_exists {
+            self.logger.log("🔧 Creating WSOL account...".yellow().to_string());
+            if let Err(e) = self.create_wsol_account_only(wallet).await {
+                self.logger.log(format!("❌ Failed to create WSOL account: {}", e).red().to_string());
+                return Err(format!("Failed to create WSOL account: {}", e));
+            }
+            self.logger.log("✅ WSOL account created successfully".green().to_string());
+        }
+
+        // Create target token account if it doesn't exist
+        if !target_token_exists {
+            self.logger.log("🔧 Creating target token account...".yellow().to_string());
+            if let Err(e) = self.create_target_token_account(wallet, &target_token_mint).await {
+                self.logger.log(format!("❌ Failed to create target token account: {}", e).red().to_string());
+                return Err(format!("Failed to create target token account: {}", e));
+            }
+            self.logger.log("✅ Target token account created successfully".green().to_string());
+        }
+
+        // Check if we need to wrap SOL to WSOL
+        if wsol_balance < 0.01 && sol_balance_f64 > 0.05 {
+            // Calculate amount to wrap based on user's requirements
+            // If we have SOL balance similar to the user's (0.001205), wrap 85% of it
+            let fee_reserve = 0.0005; // Reserve for transaction fees
+            let available_sol = sol_balance_f64 - fee_reserve;
+            let wrap_amount = if available_sol > 0.001 {
+                available_sol * 0.85 // Wrap 85% of available SOL
+            } else {
+                // Fallback to old logic for very small amounts
+                (sol_balance_f64 - 0.01) * 0.75
+            };
+            
+            if wrap_amount > 0.0005 {
+                self.logger.log(format!("🔄 Wrapping {} SOL to WSOL (85% of available balance)", wrap_amount).yellow().to_string());
+                
+                // Wrap SOL to WSOL
+                if let Err(e) = self.wrap_sol_to_wsol(wallet, wrap_amount).await {
+                    self.logger.log(format!("❌ Failed to wrap SOL to WSOL: {}", e).red().to_string());
+                    return Err(format!("Failed to wrap SOL to WSOL: {}", e));
+                }
+                
+                self.logger.log(format!("✅ Successfully wrapped {} SOL to WSOL", wrap_amount).green().to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create WSOL account and wrap SOL
+    async fn create_and_wrap_wsol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        
+        // Create WSOL account instructions
+        let (wsol_account, mut instructions) = token::create_wsol_account(wallet_pubkey)
+            .map_err(|e| format!("Failed to create WSOL account instructions: {}", e))?;
+        
+        // Convert to lamports
+        let lamports = (amount * 1_000_000_000.0) as u64;
+        
+        // Transfer SOL to the WSOL account
+        instructions.push(
+            system_instruction::transfer(
+                &wallet_pubkey,
+                &wsol_account,
+                lamports,
+            )
+        );
+        
+        // Sync native instruction
+        instructions.push(
+            sync_native(&spl_token::id(), &wsol_account)
+                .map_err(|e| format!("Failed to create sync native instruction: {}", e))?
+        );
+        
+        // Send transaction
+        let recent_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?.blockhash;
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&wallet_pubkey),
+            &[wallet],
+            recent_blockhash,
+        );
+        
+        // Account-setup transactions should wait for finalized
+        // confirmation rather than racing ahead on an unconfirmed send.
+        let submit_opts = SubmitOptions {
+            commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::finalized(),
+            ..SubmitOptions::from_config(&self.config)
+        };
+        let signature = self.submit_transaction(&transaction, submit_opts).await
+            .map_err(|e| format!("Failed to send WSOL wrap transaction: {}", e))?;
+        
+        self.logger.log(format!("✅ WSOL wrap transaction sent: {}", signature).green().to_string());
+        
+        Ok(())
+    }
+
+    /// Create WSOL account only (without wrapping)
+    async fn create_wsol_account_only(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        
+        // Create WSOL account instructions
+        let (wsol_account, instructions) = token::create_wsol_account(wallet_pubkey)
+            .map_err(|e| format!("Failed to create WSOL account instructions: {}", e))?;
+        
+        // Send transaction
+        let recent_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?.blockhash;
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&wallet_pubkey),
+            &[wallet],
+            recent_blockhash,
+        );
+        
+        // Account-setup transactions should wait for finalized
+        // confirmation rather than racing ahead on an unconfirmed send.
+        let submit_opts = SubmitOptions {
+            commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::finalized(),
+            ..SubmitOptions::from_config(&self.config)
+        };
+        let signature = self.submit_transaction(&transaction, submit_opts).await
+            .map_err(|e| format!("Failed to send WSOL account creation transaction: {}", e))?;
+        
+        self.logger.log(format!("✅ WSOL account created: {} - Signature: {}", wsol_account, signature).green().to_string());
+        
+        Ok(())
+    }
+
+    /// Create target token account
+    async fn create_target_token_account(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, token_mint: &Pubkey) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        
+        // Create associated token account instruction
+        let create_ata_instruction = spl_associated_token_account::instruction::create_associated_token_account(
+            &wallet_pubkey,  // payer
+            &wallet_pubkey,  // owner
+            token_mint,      // mint
+            &spl_token::id(), // token program
+        );
+        
+        // Send transaction
+        let recent_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?.blockhash;
+        
+        let transaction = Transaction::new_signed_with_payer(
+            &[create_ata_instruction],
+            Some(&wallet_pubkey),
+            &[wallet],
+            recent_blockhash,
+        );
+        
+        // Account-setup transactions should wait for finalized
+        // confirmation rather than racing ahead on an unconfirmed send.
+        let submit_opts = SubmitOptions {
+            commitment: anchor_client::solana_sdk::commitment_config::CommitmentConfig::finalized(),
+            ..SubmitOptions::from_config(&self.config)
+        };
+        let signature = self.submit_transaction(&transaction, submit_opts).await
+            .map_err(|e| format!("Failed to send target token account creation transaction: {}", e))?;
+        
+        let target_token_account = get_associated_token_address(&wallet_pubkey, token_mint);
+        self.logger.log(format!("✅ Target token account created: {} - Signature: {}", target_token_account, signature).green().to_string());
+        
+        Ok(())
+    }
+
+    /// Create whichever of the WSOL / target-token associated accounts
+    /// this wallet is still missing. Standalone version of the Step 1/2
+    /// account checks at the top of `execute_advanced_buy_debug`, split out
+    /// so one-off maintenance (the `ensure-accounts` CLI command) doesn't
+    /// need to run the rest of the buy flow to get them created.
+    async fn ensure_accounts(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
+
+        let wsol_exists = self.config.app_state.rpc_client.get_account(&wsol_account).is_ok();
+        let target_token_exists = self.config.app_state.rpc_client.get_account(&target_token_account).is_ok();
+
+        if !wsol_exists {
+            self.create_wsol_account_only(wallet).await?;
+        } else {
+            self.logger.log("✅ WSOL account already exists".green().to_string());
+        }
+
+        if !target_token_exists {
+            self.create_target_token_account(wallet, &target_token_mint).await?;
+        } else {
+            self.logger.log("✅ Target token account already exists".green().to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Log the wallet's SOL, WSOL, and target-token balances, the way
+    /// `execute_advanced_buy_debug` already does at the start of a buy, but
+    /// standalone for the `status` CLI command.
+    async fn log_wallet_status(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>) -> Result<(), String> {
+        let wallet_pubkey = wallet.pubkey();
+        let target_token_mint = Pubkey::from_str(&self.config.target_token_mint)
+            .map_err(|e| format!("Invalid target token mint: {}", e))?;
+
+        let wsol_account = get_associated_token_address(&wallet_pubkey, &spl_token::native_mint::id());
+        let target_token_account = get_associated_token_address(&wallet_pubkey, &target_token_mint);
+
+        let sol_balance = self.config.app_state.rpc_client.get_balance(&wallet_pubkey)
+            .map_err(|e| format!("Failed to get SOL balance: {}", e))?;
+        self.logger.log(format!("💰 SOL: {:.6}", sol_balance as f64 / 1_000_000_000.0).cyan().to_string());
+
+        match self.config.app_state.rpc_client.get_token_account_balance(&wsol_account) {
+            Ok(balance) => self.logger.log(format!("💰 WSOL: {}", balance.ui_amount_string).cyan().to_string()),
+            Err(_) => self.logger.log("💰 WSOL: account does not exist".yellow().to_string()),
+        }
+
+        match self.config.app_state.rpc_client.get_token_account_balance(&target_token_account) {
+            Ok(balance) => self.logger.log(format!("💰 Target token: {}", balance.ui_amount_string).cyan().to_string()),
+            Err(_) => self.logger.log("💰 Target token: account does not exist".yellow().to_string()),
+        }
+
+        Ok(())
+    }
+
+    /// Wrap SOL to WSOL (assuming WSOL account already exists). Thin
+    /// wrapper around `wsol_ops::wrap_sol` so the actual instruction-
+    /// building logic can be exercised against a test backend.
+    async fn wrap_sol_to_wsol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
+        let backend = wsol_ops::RpcClientBackend::new(self.config.app_state.rpc_client.clone());
+        let signature = wsol_ops::wrap_sol(&backend, wallet, amount).await
+            .map_err(|e| format!("Failed to send SOL wrap transaction: {}", e))?;
+
+        self.logger.log(format!("✅ SOL wrapped to WSOL: {} - Signature: {}", amount, signature).green().to_string());
+
+        Ok(())
+    }
+
+    /// Unwrap WSOL to SOL (for getting SOL back when needed for fees).
+    /// Thin wrapper around `wsol_ops::unwrap_wsol` so the temp-account
+    /// rent-exemption math can be exercised against a test backend.
+    async fn unwrap_wsol_to_sol(&self, wallet: &Arc<anchor_client::solana_sdk::signature::Keypair>, amount: f64) -> Result<(), String> {
+        let backend = wsol_ops::RpcClientBackend::new(self.config.app_state.rpc_client.clone());
+        let (total_lamports, signature) = wsol_ops::unwrap_wsol(&backend, wallet, amount).await
+            .map_err(|e| format!("Failed to send WSOL unwrap transaction: {}", e))?;
+
+        let lamports_to_unwrap = (amount * 1_000_000_000.0) as u64;
+        let rent_exempt_lamports = total_lamports.saturating_sub(lamports_to_unwrap);
+
+        self.logger.log(format!("✅ WSOL unwrapped to SOL: {:.6} WSOL + rent ({:.6} SOL total) - Signature: {}",
+            amount, (rent_exempt_lamports as f64 / 1_000_000_000.0), signature).green().to_string());
+
+        Ok(())
+    }
+
+    /// Process incoming GRPC messages
+    async fn process_grpc_message(&self, msg: &SubscribeUpdate) -> Result<(), String> {
+        if let Some(update_oneof) = &msg.update_oneof {
+            if let UpdateOneof::Transaction(txn_info) = update_oneof {
+                // Reconcile any pending trade this confirmation covers so the
+                // effective-balance calculation stops counting it as in-flight.
+                let mut confirmed_signature: Option<String> = None;
+                if let Some(tx) = &txn_info.transaction {
+                    if let Ok(signature) = Signature::try_from(tx.signature.as_slice()) {
+                        self.pending_ledger.confirm(&signature).await;
+                        confirmed_signature = Some(signature.to_string());
+                    }
+                }
+
+                // Parse the transaction for our target token
+                if let Some(trade_info) = parse_target_token_transaction(txn_info, &self.config.target_token_mint) {
+                    self.logger.log(format!(
+                        "🎯 Detected {} trade: User: {}, Volume: {:.6} SOL",
+                        if trade_info.is_buy { "BUY" } else { "SELL" },
+                        trade_info.user,
+                        trade_info.volume_change
+                    ).magenta().to_string());
+                    
+                    // Price the trade straight from pool reserves so the
+                    // activity report's average/min/max price are
+                    // meaningful instead of all reading as zero.
+                    let price = match self.pool_price_reader.price(&self.config.app_state.rpc_client).await {
+                        Ok(price) => {
+                            self.price_oracle.record(PriceSourceKind::PoolReserves, price, 0.9).await;
+                            price
+                        }
+                        Err(e) => {
+                            self.logger.log(format!("⚠️ Failed to read pool price for activity pricing: {}", e).yellow().to_string());
+                            0.0
+                        }
+                    };
+
+                    // Feed the depth-proxy order book from this executed trade
+                    // so quoting logic has a spread/mid-price view even though
+                    // this venue has no native resting-order feed to subscribe to.
+                    if price > 0.0 {
+                        self.order_book.record_trade(trade_info.is_buy, price, trade_info.volume_change).await;
+                    }
+
+                    // Add to activity tracking for analysis
+                    let activity = TokenActivity {
+                        timestamp: Instant::now(),
+                        is_buy: trade_info.is_buy,
+                        volume_sol: trade_info.volume_change,
+                        user: trade_info.user.clone(),
+                        price,
+                        signature: confirmed_signature,
+                    };
+                    self.add_token_activity(activity).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Submit an already-signed transaction without blocking on
+    /// `send_and_confirm_transaction`'s built-in confirmation loop, so a
+    /// slow leader can't stall a call site that doesn't actually need to
+    /// wait for finality. In `fire_and_forget` mode the signature is
+    /// returned as soon as the leader accepts the send; otherwise this
+    /// polls `get_signature_statuses` until `opts.commitment` is reached
+    /// or `opts.confirmation_timeout` elapses.
+    async fn submit_transaction(
+        &self,
+        transaction: &Transaction,
+        opts: SubmitOptions,
+    ) -> Result<Signature, SubmitError> {
+        use anchor_client::solana_client::rpc_config::RpcSendTransactionConfig;
+
+        let send_config = RpcSendTransactionConfig {
+            skip_preflight: opts.skip_preflight,
+            preflight_commitment: Some(opts.commitment.commitment),
+            max_retries: Some(opts.max_retries),
+            ..RpcSendTransactionConfig::default()
+        };
+
+        let signature = self
+            .config
+            .app_state
+            .rpc_client
+            .send_transaction_with_config(transaction, send_config)
+            .map_err(|e| SubmitError::Rpc(format!("Failed to send transaction: {}", e)))?;
+
+        if opts.fire_and_forget {
+            return Ok(signature);
+        }
+
+        let start = Instant::now();
+        loop {
+            if let Ok(statuses) = self
+                .config
+                .app_state
+                .rpc_client
+                .get_signature_statuses(&[signature])
+            {
+                if let Some(Some(status)) = statuses.value.first() {
+                    if let Some(err) = &status.err {
+                        return Err(SubmitError::Rpc(format!(
+                            "Transaction {} failed on-chain: {}",
+                            signature, err
+                        )));
+                    }
+                    if status.satisfies_commitment(opts.commitment) {
+                        return Ok(signature);
+                    }
+                }
+            }
+
+            if start.elapsed() >= opts.confirmation_timeout {
+                return Err(SubmitError::Timeout {
+                    signature,
+                    elapsed: start.elapsed(),
+                });
+            }
+
+            time::sleep(Duration::from_millis(400)).await;
+        }
+    }
+
+    /// Send transaction to the network. Routes through direct TPU
+    /// submission when `config.use_tpu` is set and the submitter
+    /// initialized cleanly, falling back to the regular RPC path otherwise.
+    async fn send_transaction(
+        &self,
+        keypair: &Arc<anchor_client::solana_sdk::signature::Keypair>,
+        instructions: Vec<anchor_client::solana_sdk::instruction::Instruction>,
+    ) -> Result<Signature, String> {
+        use anchor_client::solana_sdk::transaction::Transaction;
+        use anchor_client::solana_sdk::signer::Signer;
+
+        if self.config.use_tpu {
+            if let Some(submitter) = &self.tpu_submitter {
+                return self.send_via_tpu(submitter, keypair, &instructions).await;
+            }
+            self.logger.log("⚠️ use_tpu is set but the TPU submitter failed to initialize, falling back to RPC".yellow().to_string());
+        }
+
+        // Get recent blockhash from the background-refreshed cache instead
+        // of paying a synchronous RPC round-trip on every send.
+        let cached_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+            .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
+
+        // Create and sign transaction
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&keypair.pubkey()),
+            &[keypair.as_ref()],
+            cached_blockhash.blockhash,
+        );
+
+        // Send transaction, honoring whatever skip-preflight/retry/commitment
+        // defaults this bot was configured with.
+        let signature = self
+            .submit_transaction(&transaction, SubmitOptions::from_config(&self.config))
+            .await
+            .map_err(|e| format!("Failed to send transaction: {}", e))?;
+
+        Ok(signature)
+    }
+
+    /// Fans an already-signed transaction out over TPU and polls RPC for
+    /// confirmation, re-signing against a fresh blockhash and resending if
+    /// the original one expires before landing.
+    async fn send_via_tpu(
+        &self,
+        submitter: &tpu::TpuSubmitter,
+        keypair: &Arc<anchor_client::solana_sdk::signature::Keypair>,
+        instructions: &[anchor_client::solana_sdk::instruction::Instruction],
+    ) -> Result<Signature, String> {
+        use anchor_client::solana_sdk::transaction::Transaction;
+        use anchor_client::solana_sdk::signer::Signer;
+
+        const MAX_BLOCKHASH_RETRIES: u32 = 3;
+
+        for attempt in 1..=MAX_BLOCKHASH_RETRIES {
+            let cached_blockhash = self.blockhash_provider.latest(&self.config.app_state).await
+                .map_err(|e| format!("Failed to get recent blockhash: {}", e))?;
+
+            let transaction = Transaction::new_signed_with_payer(
+                instructions,
+                Some(&keypair.pubkey()),
+                &[keypair.as_ref()],
+                cached_blockhash.blockhash,
+            );
+
+            let sent = submitter.send_transaction(&self.config.app_state, &transaction, cached_blockhash.last_valid_block_height).await?;
+
+            // Poll for confirmation for up to 8s, but bail out as soon as
+            // the blockhash this transaction was signed against expires,
+            // since it can no longer land after that.
+            let confirmed = tokio::time::timeout(Duration::from_secs(8), async {
+                loop {
+                    if let Ok(statuses) = self.config.app_state.rpc_client.get_signature_statuses(&[sent.signature]) {
+                        if let Some(Some(status)) = statuses.value.first() {
+                            if status.err.is_none() {
+                                return true;
+                            }
+                        }
+                    }
+                    if let Ok(height) = self.config.app_state.rpc_client.get_block_height() {
+                        if blockhash_provider::BlockhashProvider::is_blockhash_expired(cached_blockhash.last_valid_block_height, height) {
+                            return false;
+                        }
+                    }
+                    time::sleep(Duration::from_millis(400)).await;
+                }
+            }).await.unwrap_or(false);
+
+            if confirmed {
+                return Ok(sent.signature);
+            }
+
+            self.logger.log(format!(
+                "⏱️ TPU send attempt {}/{} did not confirm within 8s or before its blockhash expired, retrying with a fresh blockhash",
+                attempt, MAX_BLOCKHASH_RETRIES
+            ).yellow().to_string());
+        }
+
+        Err("TPU submission exhausted blockhash retries without confirmation".to_string())
+    }
+
+    /// Get trading statistics
+    pub async fn get_trading_stats(&self) -> (u32, usize, HashMap<String, u32>) {
+        let trade_count = *self.trade_counter.lock().await;
+        let wallet_count = {
+            let wallet_pool = self.wallet_pool.lock().await;
+            wallet_pool.wallet_count()
+        };
+        let usage_stats = {
+            let wallet_pool = self.wallet_pool.lock().await;
+            wallet_pool.get_usage_stats()
+        };
+        
+        (trade_count, wallet_count, usage_stats)
+    }
+
+    /// Generate token activity analysis report
+    pub async fn generate_activity_report(&self) -> TokenActivityReport {
+        let now = Instant::now();
+        let report_window = Duration::from_secs(3600);
+
+        // Fold the last hour of activity out of the sharded store instead
+        // of draining one shared deque.
+        let recent_activities = self.token_activities.recent_within(now, report_window);
+        // A hot trader's per-shard cap can evict an entry that's still
+        // inside the window above, so the figures below may undercount
+        // rather than the trader genuinely having gone quiet.
+        let truncated = self.token_activities.truncated_within(now, report_window);
+        if truncated {
+            self.logger.log(
+                "⚠️ Activity report window exceeds at least one trader's per-shard history cap, figures may undercount"
+                    .yellow().to_string()
+            );
+        }
+
+        let now_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        if recent_activities.is_empty() {
+            let report = TokenActivityReport {
+                report_period_minutes: 60,
+                truncated,
+                ..Default::default()
+            };
+            if let Err(e) = self.trade_store.record_report(&report, now_unix).await {
+                self.logger.log(format!("⚠️ Failed to persist activity report: {}", e).yellow().to_string());
+            }
+            return report;
+        }
+
+        let total_trades = recent_activities.len() as u32;
+        let buy_trades = recent_activities.iter().filter(|a| a.is_buy).count() as u32;
+        let sell_trades = total_trades - buy_trades;
+        
+        let total_volume_sol: f64 = recent_activities.iter().map(|a| a.volume_sol).sum();
+        let buy_volume_sol: f64 = recent_activities.iter()
+            .filter(|a| a.is_buy)
+            .map(|a| a.volume_sol)
+            .sum();
+        let sell_volume_sol = total_volume_sol - buy_volume_sol;
+        
+        let prices: Vec<f64> = recent_activities.iter().map(|a| a.price).collect();
+        let average_price = prices.iter().sum::<f64>() / prices.len() as f64;
+        let min_price = prices.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max_price = prices.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        
+        let unique_traders = recent_activities
+            .iter()
+            .map(|a| &a.user)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as u32;
+
+        let report = TokenActivityReport {
+            total_trades,
+            buy_trades,
+            sell_trades,
+            total_volume_sol,
+            buy_volume_sol,
+            sell_volume_sol,
+            average_price,
+            min_price: if min_price == f64::INFINITY { 0.0 } else { min_price },
+            max_price: if max_price == f64::NEG_INFINITY { 0.0 } else { max_price },
+            unique_traders,
+            report_period_minutes: 60,
+            truncated,
+        };
+
+        if let Err(e) = self.trade_store.record_report(&report, now_unix).await {
+            self.logger.log(format!("⚠️ Failed to persist activity report: {}", e).yellow().to_string());
+        }
+
+        report
+    }
+
+    /// Up to `limit` OHLCV candles for `interval`, newest first - the
+    /// time-bucketed counterpart to `generate_activity_report`'s flat
+    /// 60-minute summary.
+    pub async fn get_candles(&self, interval: candles::Interval, limit: usize) -> Vec<candles::Candle> {
+        self.candle_aggregator.get_candles(interval, limit).await
+    }
+
+    /// Highest-priority fresh price sample the oracle currently holds, or
+    /// `None` if every source has gone stale.
+    pub async fn current_price(&self) -> Option<f64> {
+        self.price_oracle.resolve().await.map(|sample| sample.price)
+    }
+
+    /// Up to `limit` most recent raw activities across every trader shard,
+    /// newest first - the unaggregated counterpart to `get_candles`.
+    pub async fn get_recent_activities(&self, limit: usize) -> Vec<TokenActivity> {
+        self.token_activities.latest(limit)
+    }
+
+    /// Log activity report if enough time has passed
+    pub async fn check_and_log_activity_report(&self) {
+        let now = Instant::now();
+        let should_report = {
+            let mut last_report = self.last_activity_report.lock().await;
+            if now.duration_since(*last_report).as_secs() >= 1800 { // 30 minutes
+                *last_report = now;
+                true
+            } else {
+                false
+            }
+        };
+        
+        if should_report {
+            let report = self.generate_activity_report().await;
+            self.log_activity_report(&report).await;
+        }
+    }
+    
+    /// Log the activity report with detailed statistics
+    pub async fn log_activity_report(&self, report: &TokenActivityReport) {
+        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
+        self.logger.log("📊 TOKEN ACTIVITY ANALYSIS REPORT (Last 60 minutes)".cyan().bold().to_string());
+        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
+        
+        // Trade Statistics
+        self.logger.log(format!("🔢 Total Trades: {}", report.total_trades).green().to_string());
+        self.logger.log(format!("📈 Buy Trades: {} ({:.1}%)", 
+            report.buy_trades, 
+            if report.total_trades > 0 { (report.buy_trades as f64 / report.total_trades as f64) * 100.0 } else { 0.0 }
+        ).green().to_string());
+        self.logger.log(format!("📉 Sell Trades: {} ({:.1}%)", 
+            report.sell_trades,
+            if report.total_trades > 0 { (report.sell_trades as f64 / report.total_trades as f64) * 100.0 } else { 0.0 }
+        ).red().to_string());
+        
+        // Volume Statistics
+        self.logger.log(format!("💰 Total Volume: {:.6} SOL", report.total_volume_sol).cyan().to_string());
+        self.logger.log(format!("💚 Buy Volume: {:.6} SOL ({:.1}%)", 
+            report.buy_volume_sol,
+            if report.total_volume_sol > 0.0 { (report.buy_volume_sol / report.total_volume_sol) * 100.0 } else { 0.0 }
+        ).green().to_string());
+        self.logger.log(format!("💔 Sell Volume: {:.6} SOL ({:.1}%)", 
+            report.sell_volume_sol,
+            if report.total_volume_sol > 0.0 { (report.sell_volume_sol / report.total_volume_sol) * 100.0 } else { 0.0 }
+        ).red().to_string());
+        
+        // Price Statistics
+        self.logger.log(format!("📊 Average Price: ${:.8}", report.average_price).yellow().to_string());
+        self.logger.log(format!("📈 Highest Price: ${:.8}", report.max_price).green().to_string());
+        self.logger.log(format!("📉 Lowest Price: ${:.8}", report.min_price).red().to_string());
+        self.logger.log(format!("💹 Price Range: ${:.8} ({:.2}%)", 
+            report.max_price - report.min_price,
+            if report.min_price > 0.0 { ((report.max_price - report.min_price) / report.min_price) * 100.0 } else { 0.0 }
+        ).magenta().to_string());
+        
+        // Trader Statistics
+        self.logger.log(format!("👥 Unique Traders: {}", report.unique_traders).blue().to_string());
+        self.logger.log(format!("📊 Avg Trades per Trader: {:.1}", 
+            if report.unique_traders > 0 { report.total_trades as f64 / report.unique_traders as f64 } else { 0.0 }
+        ).blue().to_string());
+        
+        self.logger.log("📊 ═══════════════════════════════════════════════".cyan().bold().to_string());
+    }
+    
+    /// Add a detected token activity for analysis
+    pub async fn add_token_activity(&self, activity: TokenActivity) {
+        if activity.is_buy {
+            let mut cumulative = self.cumulative_buy_volume_sol.lock().await;
+            *cumulative += activity.volume_sol;
+        }
+
+        let now_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        self.candle_aggregator
+            .record(now_unix, activity.price, activity.volume_sol, activity.is_buy)
+            .await;
+
+        if let Some(signature) = &activity.signature {
+            let trade = persistence::TradeRecord {
+                signature: signature.clone(),
+                is_buy: activity.is_buy,
+                price: activity.price,
+                volume_sol: activity.volume_sol,
+                trader: activity.user.clone(),
+                unix_timestamp: now_unix,
+            };
+            // Persist in the background -- a Postgres round-trip on every
+            // ingested trade would gate the hot path this store was sharded
+            // to keep lock-free.
+            let trade_store = self.trade_store.clone();
+            tokio::spawn(async move {
+                if let Err(e) = trade_store.record_trade(&trade).await {
+                    warn!(signature = %trade.signature, error = %e, "failed to persist trade");
+                }
+            });
+        }
+
+        self.token_activities.record(activity.clone());
+
+        // Feed the activity's price (a pool-state read taken when the trade
+        // was observed) into the oracle, then let
+        // price monitor and guardian mode react to whatever the oracle
+        // resolves to -- not necessarily this sample, if a higher-priority
+        // source (e.g. live pool reserves) is still fresh.
+        if activity.price > 0.0 {
+            self.price_oracle.record(PriceSourceKind::GrpcTrade, activity.price, 0.7).await;
+
+            if let Some(sample) = self.price_oracle.resolve().await {
+                let mut price_monitor = self.price_monitor.lock().await;
+                price_monitor.add_price_point(sample.price, activity.volume_sol);
+                drop(price_monitor);
+
+                let mut guardian_mode = self.guardian_mode.lock().await;
+                guardian_mode.add_price_point(sample.price, activity.volume_sol);
+            }
+        }
+    }
+}
+
+/// Current wall-clock time as a Unix timestamp, used for gap-window
+/// bookkeeping around GRPC reconnects. Defaults to 0 on a clock error
+/// rather than panicking.
+fn current_unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Helper to send heartbeat pings to maintain GRPC connection
+async fn send_heartbeat_ping(
+    subscribe_tx: &Arc<tokio::sync::Mutex<impl Sink<SubscribeRequest, Error = impl std::fmt::Debug> + Unpin>>,
+) -> Result<(), String> {
+    let ping_request = SubscribeRequest {
+        ping: Some(SubscribeRequestPing { id: 0 }),
+        ..Default::default()
+    };
+    
+    let mut tx = subscribe_tx.lock().await;
+    match tx.send(ping_request).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to send ping: {:?}", e)),
+    }
+}
+
+/// Start advanced market maker with configuration
+pub async fn start_market_maker(config: MarketMakerConfig) -> Result<(), String> {
+    let market_maker = Arc::new(MarketMaker::new(config)?);
+    market_maker.start().await
+}
+
+/// Command-line front-end for one-off wallet maintenance -- wrapping SOL,
+/// unwrapping WSOL, creating accounts, checking balances, and printing the
+/// activity report -- without spinning up gRPC monitoring or the trading
+/// loop the way `start_market_maker` does.
+pub mod cli {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use clap::{Parser, Subcommand};
+    use anchor_client::solana_client::rpc_client::RpcClient;
+    use anchor_client::solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+    use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use anchor_client::solana_sdk::signature::read_keypair_file;
+    use crate::common::config::AppState;
+    use super::{MarketMaker, MarketMakerConfig};
+
+    #[derive(Parser, Debug)]
+    #[command(name = "market-maker", about = "One-off wallet maintenance for the market maker bot")]
+    pub struct Cli {
+        /// Path to the operator wallet's keypair JSON file.
+        #[arg(long, value_parser = parse_keypair_path)]
+        pub keypair: PathBuf,
+
+        /// JSON-RPC endpoint to send requests to.
+        #[arg(long = "rpc-url", value_parser = parse_rpc_url)]
+        pub rpc_url: String,
+
+        /// Commitment level for balance reads and for confirming wrap/unwrap
+        /// sends; ensure-accounts always waits for finalized.
+        #[arg(long, default_value = "confirmed", value_parser = parse_commitment)]
+        pub commitment: CommitmentConfig,
+
+        /// Mint of the token this bot trades against WSOL.
+        #[arg(long = "target-mint", value_parser = parse_pubkey)]
+        pub target_mint: Pubkey,
+
+        #[command(subcommand)]
+        pub command: Command,
+    }
+
+    #[derive(Subcommand, Debug)]
+    pub enum Command {
+        /// Wrap native SOL into WSOL.
+        Wrap {
+            #[arg(value_parser = parse_amount)]
+            amount: f64,
+        },
+        /// Unwrap WSOL back into native SOL.
+        Unwrap {
+            #[arg(value_parser = parse_amount)]
+            amount: f64,
+        },
+        /// Create the WSOL and target-token associated token accounts if missing.
+        #[command(name = "ensure-accounts")]
+        EnsureAccounts,
+        /// Print the wallet's SOL/WSOL/target-token balances.
+        Status,
+        /// Print the aggregated token activity report.
+        Report,
+    }
+
+    fn parse_keypair_path(s: &str) -> Result<PathBuf, String> {
+        let path = PathBuf::from(s);
+        if !path.is_file() {
+            return Err(format!("keypair file not found: {}", s));
+        }
+        Ok(path)
+    }
+
+    fn parse_rpc_url(s: &str) -> Result<String, String> {
+        if !(s.starts_with("http://") || s.starts_with("https://")) {
+            return Err(format!("rpc-url must be an http(s) URL, got: {}", s));
+        }
+        Ok(s.to_string())
+    }
+
+    fn parse_commitment(s: &str) -> Result<CommitmentConfig, String> {
+        match s {
+            "processed" => Ok(CommitmentConfig::processed()),
+            "confirmed" => Ok(CommitmentConfig::confirmed()),
+            "finalized" => Ok(CommitmentConfig::finalized()),
+            other => Err(format!("commitment must be one of processed|confirmed|finalized, got: {}", other)),
+        }
+    }
+
+    fn parse_pubkey(s: &str) -> Result<Pubkey, String> {
+        Pubkey::from_str(s).map_err(|e| format!("invalid pubkey {}: {}", s, e))
+    }
+
+    fn parse_amount(s: &str) -> Result<f64, String> {
+        let amount: f64 = s.parse().map_err(|_| format!("invalid amount: {}", s))?;
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(format!("amount must be a positive, finite number of SOL, got: {}", s));
+        }
+        Ok(amount)
+    }
+
+    /// Build the `MarketMaker` this CLI drives and dispatch the requested
+    /// one-off command against it. Never calls `start()`, so no gRPC
+    /// monitoring or trading loop spins up underneath a maintenance command.
+    pub async fn run(cli: Cli) -> Result<(), String> {
+        let wallet = Arc::new(
+            read_keypair_file(&cli.keypair)
+                .map_err(|e| format!("Failed to read keypair {}: {}", cli.keypair.display(), e))?,
+        );
+
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(cli.rpc_url.clone(), cli.commitment));
+        let rpc_nonblocking_client = Arc::new(NonblockingRpcClient::new_with_commitment(cli.rpc_url.clone(), cli.commitment));
+        let app_state = Arc::new(AppState {
+            rpc_client,
+            rpc_nonblocking_client,
+        });
+
+        let config = MarketMakerConfig::new(
+            String::new(),
+            String::new(),
+            app_state,
+            cli.target_mint.to_string(),
+        );
+        let market_maker = MarketMaker::new(config)?;
+
+        match cli.command {
+            Command::Wrap { amount } => market_maker.wrap_sol_to_wsol(&wallet, amount).await,
+            Command::Unwrap { amount } => market_maker.unwrap_wsol_to_sol(&wallet, amount).await,
+            Command::EnsureAccounts => market_maker.ensure_accounts(&wallet).await,
+            Command::Status => market_maker.log_wallet_status(&wallet).await,
+            Command::Report => {
+                let report = market_maker.generate_activity_report().await;
+                println!("{:#?}", report);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Embeddable facade over `MarketMaker` for hosts that want to drive this
+/// engine from inside a larger process (a dashboard, an orchestrator)
+/// instead of running the CLI binary. Takes an rpc-url and raw keypair
+/// bytes directly rather than the `AppState`/`MarketMakerConfig` wiring
+/// the binary builds for itself, since an embedding host rarely has
+/// either lying around - and nothing here starts the gRPC monitoring
+/// loop, only the maintenance/reporting surface `python_bindings` and
+/// `node_bindings` expose across the language boundary.
+pub mod embedded {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use anchor_client::solana_client::rpc_client::RpcClient;
+    use anchor_client::solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+    use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+    use anchor_client::solana_sdk::signature::Keypair;
+    use crate::common::config::AppState;
+    use super::{MarketMaker, MarketMakerConfig, TokenActivityReport};
+
+    /// A ready-to-drive engine plus the wallet that signs for it. Cheap to
+    /// clone - everything inside is already `Arc`-wrapped - so binding
+    /// layers can hand a clone across a language boundary per call instead
+    /// of juggling lifetimes.
+    #[derive(Clone)]
+    pub struct EmbeddedEngine {
+        market_maker: Arc<MarketMaker>,
+        wallet: Arc<Keypair>,
+    }
+
+    impl EmbeddedEngine {
+        /// Builds an engine straight from an RPC endpoint, raw keypair
+        /// bytes (the 64-byte secret key format `Keypair::from_bytes`
+        /// expects) and the target mint - no config file or pre-existing
+        /// `AppState` required.
+        pub fn new(rpc_url: &str, keypair_bytes: &[u8], target_mint: &str) -> Result<Self, String> {
+            let wallet = Arc::new(
+                Keypair::from_bytes(keypair_bytes)
+                    .map_err(|e| format!("Invalid keypair bytes: {}", e))?,
+            );
+
+            let rpc_client = Arc::new(RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()));
+            let rpc_nonblocking_client = Arc::new(NonblockingRpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()));
+            let app_state = Arc::new(AppState { rpc_client, rpc_nonblocking_client });
+
+            let config = MarketMakerConfig::new(String::new(), String::new(), app_state, target_mint.to_string());
+            let market_maker = Arc::new(MarketMaker::new(config)?);
+
+            Ok(Self { market_maker, wallet })
+        }
+
+        pub async fn ensure_accounts(&self) -> Result<(), String> {
+            self.market_maker.ensure_accounts(&self.wallet).await
+        }
+
+        pub async fn wrap(&self, amount: f64) -> Result<(), String> {
+            self.market_maker.wrap_sol_to_wsol(&self.wallet, amount).await
+        }
+
+        pub async fn unwrap(&self, amount: f64) -> Result<(), String> {
+            self.market_maker.unwrap_wsol_to_sol(&self.wallet, amount).await
+        }
+
+        pub async fn get_trading_stats(&self) -> (u32, usize, HashMap<String, u32>) {
+            self.market_maker.get_trading_stats().await
+        }
+
+        pub async fn generate_activity_report(&self) -> TokenActivityReport {
+            self.market_maker.generate_activity_report().await
+        }
+    }
+}
+
+/// Python bindings via pyo3, compiled only when the crate's `python`
+/// feature is enabled. Async entry points hand their future to
+/// `pyo3_asyncio` rather than blocking the Python thread, and every
+/// `String` error this engine can return crosses over as a
+/// `RuntimeError` rather than a panic.
+#[cfg(feature = "python")]
+pub mod python_bindings {
+    use pyo3::exceptions::PyRuntimeError;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use super::embedded::EmbeddedEngine;
+
+    #[pyclass(name = "MarketMakerEngine")]
+    pub struct PyMarketMakerEngine {
+        inner: EmbeddedEngine,
+    }
+
+    #[pymethods]
+    impl PyMarketMakerEngine {
+        #[new]
+        fn new(rpc_url: &str, keypair_bytes: Vec<u8>, target_mint: &str) -> PyResult<Self> {
+            let inner = EmbeddedEngine::new(rpc_url, &keypair_bytes, target_mint)
+                .map_err(PyRuntimeError::new_err)?;
+            Ok(Self { inner })
+        }
+
+        fn ensure_accounts<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+            let engine = self.inner.clone();
+            pyo3_asyncio::tokio::future_into_py(py, async move {
+                engine.ensure_accounts().await.map_err(PyRuntimeError::new_err)
+            })
+        }
+
+        fn wrap<'p>(&self, py: Python<'p>, amount: f64) -> PyResult<&'p PyAny> {
+            let engine = self.inner.clone();
+            pyo3_asyncio::tokio::future_into_py(py, async move {
+                engine.wrap(amount).await.map_err(PyRuntimeError::new_err)
+            })
+        }
+
+        #[pyo3(name = "unwrap")]
+        fn unwrap_wsol<'p>(&self, py: Python<'p>, amount: f64) -> PyResult<&'p PyAny> {
+            let engine = self.inner.clone();
+            pyo3_asyncio::tokio::future_into_py(py, async move {
+                engine.unwrap(amount).await.map_err(PyRuntimeError::new_err)
+            })
+        }
+
+        fn get_trading_stats<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+            let engine = self.inner.clone();
+            pyo3_asyncio::tokio::future_into_py(py, async move {
+                let (trade_counter, wallet_count, wallet_activity) = engine.get_trading_stats().await;
+                Python::with_gil(|py| {
+                    let dict = PyDict::new(py);
+                    dict.set_item("trade_counter", trade_counter)?;
+                    dict.set_item("wallet_count", wallet_count)?;
+                    dict.set_item("wallet_activity", wallet_activity)?;
+                    Ok(dict.into())
+                })
+            })
+        }
+
+        fn generate_activity_report<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+            let engine = self.inner.clone();
+            pyo3_asyncio::tokio::future_into_py(py, async move {
+                let report = engine.generate_activity_report().await;
+                Python::with_gil(|py| {
+                    let dict = PyDict::new(py);
+                    dict.set_item("total_trades", report.total_trades)?;
+                    dict.set_item("buy_trades", report.buy_trades)?;
+                    dict.set_item("sell_trades", report.sell_trades)?;
+                    dict.set_item("total_volume_sol", report.total_volume_sol)?;
+                    dict.set_item("buy_volume_sol", report.buy_volume_sol)?;
+                    dict.set_item("sell_volume_sol", report.sell_volume_sol)?;
+                    dict.set_item("average_price", report.average_price)?;
+                    dict.set_item("min_price", report.min_price)?;
+                    dict.set_item("max_price", report.max_price)?;
+                    dict.set_item("unique_traders", report.unique_traders)?;
+                    dict.set_item("report_period_minutes", report.report_period_minutes)?;
+                    dict.set_item("truncated", report.truncated)?;
+                    Ok(dict.into())
+                })
+            })
+        }
+    }
+
+    /// Registers `MarketMakerEngine` on the extension module; wired up
+    /// from the crate's top-level `#[pymodule]` entry point.
+    pub fn register(m: &PyModule) -> PyResult<()> {
+        m.add_class::<PyMarketMakerEngine>()?;
+        Ok(())
+    }
+}
+
+/// Node bindings via neon, compiled only when the crate's `nodejs`
+/// feature is enabled. Every async entry point runs on a dedicated
+/// Tokio runtime and settles a JS promise through neon's channel, since
+/// neon's `FunctionContext` itself isn't `Send` and can't cross an
+/// `.await`.
+#[cfg(feature = "nodejs")]
+pub mod node_bindings {
+    use std::sync::OnceLock;
+    use neon::prelude::*;
+    use neon::types::JsPromise;
+    use tokio::runtime::Runtime;
+    use super::embedded::EmbeddedEngine;
+
+    fn runtime() -> &'static Runtime {
+        static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+        RUNTIME.get_or_init(|| Runtime::new().expect("failed to start Tokio runtime for node bindings"))
+    }
+
+    struct JsEngine(EmbeddedEngine);
+    impl Finalize for JsEngine {}
+
+    fn engine_new(mut cx: FunctionContext) -> JsResult<JsBox<JsEngine>> {
+        let rpc_url = cx.argument::<JsString>(0)?.value(&mut cx);
+        let keypair_bytes = cx.argument::<JsBuffer>(1)?.as_slice(&cx).to_vec();
+        let target_mint = cx.argument::<JsString>(2)?.value(&mut cx);
+
+        match EmbeddedEngine::new(&rpc_url, &keypair_bytes, &target_mint) {
+            Ok(engine) => Ok(cx.boxed(JsEngine(engine))),
+            Err(e) => cx.throw_error(e),
+        }
+    }
+
+    fn engine_ensure_accounts(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let engine = cx.argument::<JsBox<JsEngine>>(0)?.0.clone();
+        let channel = cx.channel();
+        let (deferred, promise) = cx.promise();
+
+        runtime().spawn(async move {
+            let result = engine.ensure_accounts().await;
+            deferred.settle_with(&channel, move |mut cx| {
+                result.map(|_| cx.undefined()).or_else(|e| cx.throw_error(e))
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn engine_wrap(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let engine = cx.argument::<JsBox<JsEngine>>(0)?.0.clone();
+        let amount = cx.argument::<JsNumber>(1)?.value(&mut cx);
+        let channel = cx.channel();
+        let (deferred, promise) = cx.promise();
+
+        runtime().spawn(async move {
+            let result = engine.wrap(amount).await;
+            deferred.settle_with(&channel, move |mut cx| {
+                result.map(|_| cx.undefined()).or_else(|e| cx.throw_error(e))
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn engine_unwrap(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let engine = cx.argument::<JsBox<JsEngine>>(0)?.0.clone();
+        let amount = cx.argument::<JsNumber>(1)?.value(&mut cx);
+        let channel = cx.channel();
+        let (deferred, promise) = cx.promise();
+
+        runtime().spawn(async move {
+            let result = engine.unwrap(amount).await;
+            deferred.settle_with(&channel, move |mut cx| {
+                result.map(|_| cx.undefined()).or_else(|e| cx.throw_error(e))
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn engine_get_trading_stats(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let engine = cx.argument::<JsBox<JsEngine>>(0)?.0.clone();
+        let channel = cx.channel();
+        let (deferred, promise) = cx.promise();
+
+        runtime().spawn(async move {
+            let (trade_counter, wallet_count, wallet_activity) = engine.get_trading_stats().await;
+            deferred.settle_with(&channel, move |mut cx| {
+                let obj = cx.empty_object();
+                let trade_counter_js = cx.number(trade_counter as f64);
+                obj.set(&mut cx, "tradeCounter", trade_counter_js)?;
+                let wallet_count_js = cx.number(wallet_count as f64);
+                obj.set(&mut cx, "walletCount", wallet_count_js)?;
+                let wallet_activity_js = cx.empty_object();
+                for (wallet, count) in wallet_activity {
+                    let count_js = cx.number(count as f64);
+                    wallet_activity_js.set(&mut cx, wallet.as_str(), count_js)?;
+                }
+                obj.set(&mut cx, "walletActivity", wallet_activity_js)?;
+                Ok(obj)
+            });
+        });
+
+        Ok(promise)
+    }
+
+    fn engine_generate_activity_report(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let engine = cx.argument::<JsBox<JsEngine>>(0)?.0.clone();
+        let channel = cx.channel();
+        let (deferred, promise) = cx.promise();
+
+        runtime().spawn(async move {
+            let report = engine.generate_activity_report().await;
+            deferred.settle_with(&channel, move |mut cx| {
+                let obj = cx.empty_object();
+                let total_trades = cx.number(report.total_trades);
+                obj.set(&mut cx, "totalTrades", total_trades)?;
+                let buy_trades = cx.number(report.buy_trades);
+                obj.set(&mut cx, "buyTrades", buy_trades)?;
+                let sell_trades = cx.number(report.sell_trades);
+                obj.set(&mut cx, "sellTrades", sell_trades)?;
+                let total_volume_sol = cx.number(report.total_volume_sol);
+                obj.set(&mut cx, "totalVolumeSol", total_volume_sol)?;
+                let buy_volume_sol = cx.number(report.buy_volume_sol);
+                obj.set(&mut cx, "buyVolumeSol", buy_volume_sol)?;
+                let sell_volume_sol = cx.number(report.sell_volume_sol);
+                obj.set(&mut cx, "sellVolumeSol", sell_volume_sol)?;
+                let average_price = cx.number(report.average_price);
+                obj.set(&mut cx, "averagePrice", average_price)?;
+                let min_price = cx.number(report.min_price);
+                obj.set(&mut cx, "minPrice", min_price)?;
+                let max_price = cx.number(report.max_price);
+                obj.set(&mut cx, "maxPrice", max_price)?;
+                let unique_traders = cx.number(report.unique_traders);
+                obj.set(&mut cx, "uniqueTraders", unique_traders)?;
+                let report_period_minutes = cx.number(report.report_period_minutes as f64);
+                obj.set(&mut cx, "reportPeriodMinutes", report_period_minutes)?;
+                let truncated = cx.boolean(report.truncated);
+                obj.set(&mut cx, "truncated", truncated)?;
+                Ok(obj)
+            });
+        });
+
+        Ok(promise)
+    }
+
+    /// Registers the `engine*` functions on the native module; called from
+    /// the crate's `#[neon::main]` entry point.
+    pub fn register(cx: &mut ModuleContext) -> NeonResult<()> {
+        cx.export_function("engineNew", engine_new)?;
+        cx.export_function("engineEnsureAccounts", engine_ensure_accounts)?;
+        cx.export_function("engineWrap", engine_wrap)?;
+        cx.export_function("engineUnwrap", engine_unwrap)?;
+        cx.export_function("engineGetTradingStats", engine_get_trading_stats)?;
+        cx.export_function("engineGenerateActivityReport", engine_generate_activity_report)?;
+        Ok(())
+    }
 } 
\ No newline at end of file